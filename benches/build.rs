@@ -41,4 +41,36 @@ mod ng {
             assert_eq!(s, EXPECTED);
         });
     }
+
+    fn with_max_age() -> impl cookies::Cookie {
+        Builder::new("hello", "mynameiswat")
+            .path("/")
+            .domain("hyper.rs")
+            .max_age(std::time::Duration::from_secs(3600))
+            .build()
+            .unwrap()
+    }
+
+    #[bench]
+    fn to_string_max_age(b: &mut test::Bencher) {
+        let c = with_max_age();
+        b.bytes = c.to_string().len() as u64;
+
+        b.iter(|| test::black_box(c.to_string()));
+    }
+
+    #[bench]
+    fn write_set_cookie_max_age(b: &mut test::Bencher) {
+        use bytes::BufMut;
+
+        let c = with_max_age();
+        b.bytes = c.to_string().len() as u64;
+        let mut dst = bytes::BytesMut::with_capacity(128);
+
+        b.iter(|| {
+            dst.clear();
+            cookies::write_set_cookie(&c, &mut (&mut dst).writer()).unwrap();
+            test::black_box(&dst);
+        });
+    }
 }
@@ -0,0 +1,476 @@
+//! Read and write Apple's `Cookies.binarycookies` format.
+//!
+//! This is the format Safari, and apps on iOS and macOS, store their cookies
+//! in. The file is made of pages of cookie records. Page headers are
+//! big-endian, records are little-endian, and dates are floating-point
+//! seconds since 2001-01-01, the Mac epoch.
+//!
+//! Requires the `jar` feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use cookies::{binarycookies, CookieJar};
+//!
+//! let bytes = std::fs::read("Cookies.binarycookies").unwrap();
+//!
+//! let mut jar = CookieJar::new();
+//! for cookie in binarycookies::parse(&bytes).unwrap() {
+//!     // Cookies no site could set, such as for `com`, are rejected.
+//!     let _ = jar.insert(cookie);
+//! }
+//!
+//! let mut out = Vec::new();
+//! binarycookies::write(jar.iter(), &mut out).unwrap();
+//! ```
+
+use std::convert::TryInto;
+use std::fmt;
+use std::io;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::parse::{self, Domain};
+use crate::util::saturating_add;
+use crate::StoredCookie;
+
+/// An error for a malformed `Cookies.binarycookies` file.
+#[derive(Debug)]
+pub struct Error {
+    kind: Kind,
+    offset: usize,
+}
+
+#[derive(Debug)]
+enum Kind {
+    Magic,
+    Eof,
+    PageHeader,
+    Record,
+    Checksum,
+}
+
+const MAGIC: &[u8; 4] = b"cook";
+const PAGE_HEADER: [u8; 4] = [0, 0, 1, 0];
+const FOOTER: [u8; 8] = [0x07, 0x17, 0x20, 0x05, 0x00, 0x00, 0x00, 0x4b];
+const RECORD_HEADER_LEN: usize = 56;
+
+const FLAG_SECURE: u32 = 1;
+const FLAG_HTTP_ONLY: u32 = 4;
+
+// Seconds between the Unix epoch and 2001-01-01, the Mac epoch.
+const MAC_EPOCH_OFFSET: f64 = 978_307_200.0;
+
+/// Parse the cookies of a `Cookies.binarycookies` file.
+///
+/// Records that aren't valid cookies, such as with a value this crate
+/// doesn't allow, are skipped.
+///
+/// # Error
+///
+/// Returns an error if the file is truncated, or isn't in this format.
+pub fn parse(src: &[u8]) -> Result<Vec<StoredCookie>, Error> {
+    let mut reader = Reader { src, pos: 0 };
+    if reader.bytes(4)? != MAGIC {
+        return Err(Error::at(Kind::Magic, 0));
+    }
+
+    let page_count = reader.u32_be()? as usize;
+    let mut page_sizes = Vec::new();
+    for _ in 0..page_count {
+        page_sizes.push(reader.u32_be()? as usize);
+    }
+
+    let mut cookies = Vec::new();
+    let mut checksum = 0u32;
+    for size in page_sizes {
+        let start = reader.pos;
+        let page = reader.bytes(size)?;
+        checksum = page
+            .iter()
+            .step_by(4)
+            .fold(checksum, |sum, &b| sum.wrapping_add(u32::from(b)));
+        parse_page(page, start, &mut cookies)?;
+    }
+
+    let checksum_at = reader.pos;
+    if reader.u32_be()? != checksum {
+        return Err(Error::at(Kind::Checksum, checksum_at));
+    }
+    // The footer may be followed by a property list of the cookie policy,
+    // which is ignored.
+    Ok(cookies)
+}
+
+fn parse_page(page: &[u8], start: usize, cookies: &mut Vec<StoredCookie>) -> Result<(), Error> {
+    let mut reader = Reader { src: page, pos: 0 };
+    if reader.bytes(4).map_err(|e| e.shift(start))? != PAGE_HEADER {
+        return Err(Error::at(Kind::PageHeader, start));
+    }
+
+    let count = reader.u32_le().map_err(|e| e.shift(start))? as usize;
+    for _ in 0..count {
+        let offset = reader.u32_le().map_err(|e| e.shift(start))? as usize;
+        let record = parse_record(page, offset).map_err(|e| e.shift(start))?;
+        cookies.extend(record);
+    }
+    Ok(())
+}
+
+/// Parse the record at `offset` of a page, or `None` if it isn't a valid
+/// cookie.
+fn parse_record(page: &[u8], offset: usize) -> Result<Option<StoredCookie>, Error> {
+    let mut reader = Reader {
+        src: page,
+        pos: offset,
+    };
+    let size = reader.u32_le()? as usize;
+    if size < RECORD_HEADER_LEN {
+        return Err(Error::at(Kind::Record, offset));
+    }
+    let record = offset
+        .checked_add(size)
+        .and_then(|end| page.get(offset..end))
+        .ok_or_else(|| Error::at(Kind::Eof, page.len()))?;
+
+    let mut reader = Reader {
+        src: record,
+        pos: 8,
+    };
+    let flags = reader.u32_le()?;
+    reader.pos += 4;
+    let domain_at = reader.u32_le()? as usize;
+    let name_at = reader.u32_le()? as usize;
+    let path_at = reader.u32_le()? as usize;
+    let value_at = reader.u32_le()? as usize;
+    reader.pos += 8;
+    let expiry = reader.f64_le()?;
+    let creation = reader.f64_le()?;
+
+    let string = |at: usize| -> Result<&str, Error> {
+        let bytes = record
+            .get(at..)
+            .filter(|_| at >= RECORD_HEADER_LEN)
+            .ok_or_else(|| Error::at(Kind::Record, offset))?;
+        let len = bytes
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(|| Error::at(Kind::Record, offset))?;
+        std::str::from_utf8(&bytes[..len]).map_err(|_| Error::at(Kind::Record, offset + at))
+    };
+    let domain = string(domain_at)?;
+    let name = string(name_at)?;
+    let path = string(path_at)?;
+    let value = string(value_at)?;
+
+    if parse::validate_name(name).is_err()
+        || parse::validate_value(value).is_err()
+        || !parse::is_valid_path(path)
+    {
+        return Ok(None);
+    }
+    let (domain, host_only) = match parse::validate_domain(domain) {
+        Domain::AsIs => (domain, true),
+        Domain::LeadingDot => (&domain[1..], false),
+        Domain::Invalid => return Ok(None),
+    };
+
+    let mut cookie = StoredCookie::new(
+        name.to_owned(),
+        value.to_owned(),
+        domain.to_ascii_lowercase(),
+        path.to_owned(),
+    );
+    cookie.expiry = Some(from_mac(expiry));
+    cookie.creation = from_mac(creation);
+    cookie.host_only = host_only;
+    cookie.secure = flags & FLAG_SECURE != 0;
+    cookie.http_only = flags & FLAG_HTTP_ONLY != 0;
+    Ok(Some(cookie))
+}
+
+/// Write cookies in the `Cookies.binarycookies` format, with a page for
+/// each domain.
+///
+/// The format only has persistent, unpartitioned cookies, so session and
+/// partitioned cookies are skipped.
+///
+/// # Error
+///
+/// Returns any error from writing into `dst`.
+pub fn write<'a, W>(
+    cookies: impl IntoIterator<Item = &'a StoredCookie>,
+    dst: &mut W,
+) -> io::Result<()>
+where
+    W: io::Write + ?Sized,
+{
+    // Pages are in the order their domain is first seen.
+    let mut by_domain = Vec::<(&str, Vec<&StoredCookie>)>::new();
+    for c in cookies {
+        if c.expiry.is_none() || c.partition_key.is_some() {
+            continue;
+        }
+        match by_domain.iter_mut().find(|(domain, _)| *domain == c.domain) {
+            Some((_, page)) => page.push(c),
+            None => by_domain.push((&c.domain, vec![c])),
+        }
+    }
+
+    let pages = by_domain
+        .iter()
+        .map(|(_, cookies)| write_page(cookies))
+        .collect::<Vec<_>>();
+
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&(pages.len() as u32).to_be_bytes());
+    for page in &pages {
+        out.extend_from_slice(&(page.len() as u32).to_be_bytes());
+    }
+    let mut checksum = 0u32;
+    for page in &pages {
+        checksum = page
+            .iter()
+            .step_by(4)
+            .fold(checksum, |sum, &b| sum.wrapping_add(u32::from(b)));
+        out.extend_from_slice(page);
+    }
+    out.extend_from_slice(&checksum.to_be_bytes());
+    out.extend_from_slice(&FOOTER);
+
+    dst.write_all(&out)
+}
+
+fn write_page(cookies: &[&StoredCookie]) -> Vec<u8> {
+    let records = cookies.iter().map(|c| write_record(c)).collect::<Vec<_>>();
+
+    let mut page = Vec::new();
+    page.extend_from_slice(&PAGE_HEADER);
+    page.extend_from_slice(&(records.len() as u32).to_le_bytes());
+    let mut offset = 4 + 4 + 4 * records.len() + 4;
+    for record in &records {
+        page.extend_from_slice(&(offset as u32).to_le_bytes());
+        offset += record.len();
+    }
+    page.extend_from_slice(&[0; 4]);
+    for record in &records {
+        page.extend_from_slice(record);
+    }
+    page
+}
+
+fn write_record(c: &StoredCookie) -> Vec<u8> {
+    let domain = if c.host_only {
+        c.domain.clone()
+    } else {
+        format!(".{}", c.domain)
+    };
+
+    let mut strings = Vec::new();
+    let mut offsets = [0u32; 4];
+    for (i, s) in [&domain[..], &c.name, &c.path, &c.value].iter().enumerate() {
+        offsets[i] = (RECORD_HEADER_LEN + strings.len()) as u32;
+        strings.extend_from_slice(s.as_bytes());
+        strings.push(0);
+    }
+
+    let mut flags = 0;
+    if c.secure {
+        flags |= FLAG_SECURE;
+    }
+    if c.http_only {
+        flags |= FLAG_HTTP_ONLY;
+    }
+
+    let mut record = Vec::with_capacity(RECORD_HEADER_LEN + strings.len());
+    record.extend_from_slice(&((RECORD_HEADER_LEN + strings.len()) as u32).to_le_bytes());
+    record.extend_from_slice(&[0; 4]);
+    record.extend_from_slice(&flags.to_le_bytes());
+    record.extend_from_slice(&[0; 4]);
+    for offset in &offsets {
+        record.extend_from_slice(&offset.to_le_bytes());
+    }
+    record.extend_from_slice(&[0; 8]);
+    let expiry = c.expiry.expect("only persistent cookies are written");
+    record.extend_from_slice(&to_mac(expiry).to_le_bytes());
+    record.extend_from_slice(&to_mac(c.creation).to_le_bytes());
+    record.extend_from_slice(&strings);
+    record
+}
+
+fn from_mac(secs: f64) -> SystemTime {
+    let unix = secs + MAC_EPOCH_OFFSET;
+    match Duration::try_from_secs_f64(unix) {
+        Ok(since) => saturating_add(UNIX_EPOCH, since),
+        Err(_) if unix > 0.0 => saturating_add(UNIX_EPOCH, Duration::MAX),
+        Err(_) => UNIX_EPOCH,
+    }
+}
+
+fn to_mac(time: SystemTime) -> f64 {
+    let unix = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::from_secs(0));
+    unix.as_secs_f64() - MAC_EPOCH_OFFSET
+}
+
+struct Reader<'a> {
+    src: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let bytes = self
+            .pos
+            .checked_add(len)
+            .and_then(|end| self.src.get(self.pos..end))
+            .ok_or_else(|| Error::at(Kind::Eof, self.src.len()))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        Ok(self.bytes(N)?.try_into().expect("N bytes"))
+    }
+
+    fn u32_be(&mut self) -> Result<u32, Error> {
+        self.array().map(u32::from_be_bytes)
+    }
+
+    fn u32_le(&mut self) -> Result<u32, Error> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn f64_le(&mut self) -> Result<f64, Error> {
+        self.array().map(f64::from_le_bytes)
+    }
+}
+
+// ===== impl Error =====
+
+impl Error {
+    fn at(kind: Kind, offset: usize) -> Error {
+        Error { kind, offset }
+    }
+
+    /// Make the offset of an error in a page relative to the file.
+    fn shift(mut self, start: usize) -> Error {
+        self.offset += start;
+        self
+    }
+
+    /// Get the offset in the file where the error is, in bytes.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let msg = match self.kind {
+            Kind::Magic => "not a binarycookies file",
+            Kind::Eof => "unexpected end of file",
+            Kind::PageHeader => "invalid page header",
+            Kind::Record => "invalid cookie record",
+            Kind::Checksum => "checksum mismatch",
+        };
+        write!(f, "{} at byte {}", msg, self.offset)
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cookie;
+
+    fn cookie(name: &str, domain: &str, host_only: bool) -> StoredCookie {
+        let mut c = StoredCookie::new(name.into(), "v".into(), domain.into(), "/".into());
+        c.host_only = host_only;
+        c.expiry = Some(UNIX_EPOCH + Duration::from_secs(4_102_444_800));
+        c.creation = UNIX_EPOCH + Duration::from_secs(1_690_000_000);
+        c
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut a = cookie("a", "hyper.rs", false);
+        a.secure = true;
+        a.http_only = true;
+        let b = cookie("b", "hyper.rs", false);
+        let c = cookie("c", "www.rust-lang.org", true);
+        let mut session = cookie("session", "hyper.rs", true);
+        session.expiry = None;
+
+        let mut out = Vec::new();
+        write(&[a, b, c, session], &mut out).unwrap();
+
+        let parsed = parse(&out).unwrap();
+        let names = parsed.iter().map(|c| c.name()).collect::<Vec<_>>();
+        assert_eq!(names, ["a", "b", "c"], "session cookie is skipped");
+
+        assert!(parsed[0].secure());
+        assert!(parsed[0].http_only());
+        assert!(!parsed[0].host_only());
+        assert!(!parsed[1].secure());
+        assert!(parsed[2].host_only());
+        assert_eq!(
+            parsed[2].expires(),
+            Some(UNIX_EPOCH + Duration::from_secs(4_102_444_800))
+        );
+        assert_eq!(
+            parsed[2].creation_time(),
+            UNIX_EPOCH + Duration::from_secs(1_690_000_000)
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        let err = parse(b"nope").unwrap_err();
+        assert_eq!(err.to_string(), "not a binarycookies file at byte 0");
+
+        let err = parse(b"cook\0\0\0\x01\0\0\0\x10").unwrap_err();
+        assert_eq!(err.to_string(), "unexpected end of file at byte 12");
+
+        let mut out = Vec::new();
+        write(&[cookie("a", "hyper.rs", true)], &mut out).unwrap();
+        let len = out.len();
+        out[len - 9] ^= 1;
+        let err = parse(&out).unwrap_err();
+        assert_eq!(err.offset(), len - 12, "checksum");
+    }
+
+    #[test]
+    fn hostile_dates() {
+        // The expiry of the only record, after the file and page headers.
+        const EXPIRY_AT: usize = 12 + 16 + 40;
+
+        for &expiry in &[1e19, -1e19, f64::INFINITY, f64::NAN] {
+            let mut out = Vec::new();
+            write(&[cookie("a", "hyper.rs", true)], &mut out).unwrap();
+            out[EXPIRY_AT..EXPIRY_AT + 8].copy_from_slice(&f64::to_le_bytes(expiry));
+
+            let page_len = out.len() - 12 - 4 - FOOTER.len();
+            let checksum = out[12..12 + page_len]
+                .iter()
+                .step_by(4)
+                .fold(0u32, |sum, &b| sum.wrapping_add(u32::from(b)));
+            out[12 + page_len..16 + page_len].copy_from_slice(&checksum.to_be_bytes());
+
+            let parsed = parse(&out).unwrap();
+            let expires = parsed[0].expires().unwrap();
+            if expiry > 0.0 {
+                assert_eq!(
+                    expires,
+                    UNIX_EPOCH + Duration::from_secs(253_402_300_799),
+                    "{}",
+                    expiry
+                );
+            } else {
+                assert_eq!(expires, UNIX_EPOCH, "{}", expiry);
+            }
+        }
+    }
+}
@@ -1,29 +1,48 @@
 use std::fmt;
-use std::time::Duration;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, SystemTime};
 
-use super::{Cookie, Error, SameSite, Sealed};
+use super::{Cookie, Error, Priority, SameSite, Sealed};
+use crate::codec::Codec;
 use crate::util;
 
 /// Configure an HTTP cookie with the builder pattern.
 #[derive(Debug)]
 pub struct Builder<C> {
     state: Result<C, Error>,
+    max_len: Option<usize>,
+    // Whether the cookie must be short enough to be parsed again.
+    parseable: bool,
+    // Set by `build` when the expiry is pinned.
+    pinned_at: Option<PinnedAt>,
 }
 
 struct Pair<N, V>(N, V);
 
 struct WithValue<C, V>(C, V);
 
-struct WithPath<C, P>(C, P);
+struct WithPath<C, P>(C, Option<P>);
 
-struct WithDomain<C, D>(C, D);
+struct WithDomain<C, D>(C, Option<D>);
 
-struct WithMaxAge<C>(C, Duration);
+type PinnedAt = Arc<OnceLock<SystemTime>>;
+
+struct WithMaxAge<C>(C, Duration, Option<PinnedAt>);
+
+struct WithExpires<C>(C, Option<SystemTime>);
+
+struct WithPinnedExpiry<C>(C, PinnedAt);
 
 struct WithSecure<C>(C, bool);
 
 struct WithHttpOnly<C>(C, bool);
 
+struct WithSameSite<C>(C, Option<SameSite>);
+
+struct WithPriority<C>(C, Option<Priority>);
+
+struct WithPartitioned<C>(C, bool);
+
 // ===== impl Builder =====
 
 impl Builder<()> {
@@ -33,6 +52,9 @@ impl Builder<()> {
         let state = state.and_then(|()| crate::parse::validate_value(value.as_ref()));
         Builder {
             state: state.map(move |()| Pair(name, value)),
+            max_len: None,
+            parseable: false,
+            pinned_at: None,
         }
     }
 }
@@ -40,7 +62,12 @@ impl Builder<()> {
 impl<C: Cookie> Builder<C> {
     /// Wrap an existing `Cookie` in a builder, in order to change attributes.
     pub fn wrap(cookie: C) -> Builder<C> {
-        Builder { state: Ok(cookie) }
+        Builder {
+            state: Ok(cookie),
+            max_len: None,
+            parseable: false,
+            pinned_at: None,
+        }
     }
 
     /// Set the value of this cookie.
@@ -53,35 +80,109 @@ impl<C: Cookie> Builder<C> {
         })
     }
 
+    /// Set the value of this cookie, encoding raw bytes with a `Codec`.
+    ///
+    /// Use `Cookie::decoded_value` with the same codec to read it back.
+    pub fn encoded_value(self, codec: impl Codec, raw: impl AsRef<[u8]>) -> Builder<impl Cookie> {
+        self.and_then(move |c| {
+            let value = codec.encode(raw.as_ref()).into_owned();
+            crate::parse::validate_value(&value)?;
+            Ok(util::Delegated(WithValue(c, value)))
+        })
+    }
+
+    /// Set the value of this cookie to a serialized `T`.
+    ///
+    /// The value is encoded as compact JSON, then with `codec::Base64Url`.
+    /// Use `Cookie::typed_value` to read it back.
+    ///
+    /// # Error
+    ///
+    /// Returns an error if `T` fails to serialize, or if the cookie would
+    /// be too long to be parsed again.
+    #[cfg(feature = "serde")]
+    pub fn typed_value<T: serde::Serialize + ?Sized>(mut self, value: &T) -> Builder<impl Cookie> {
+        let json = serde_json::to_vec(value).map_err(|_| Error::invalid_value());
+        // Checked in `build`, once all the attributes are set.
+        self.parseable = true;
+        self.and_then(move |c| {
+            let value = crate::codec::Base64Url.encode(&json?).into_owned();
+            Ok(util::Delegated(WithValue(c, value)))
+        })
+    }
+
     /// Set the `Path` attribute of this cookie.
     pub fn path(self, path: impl AsRef<str>) -> Builder<impl Cookie> {
+        self.maybe_path(Some(path))
+    }
+
+    /// Set the `Path` attribute of this cookie, if there is one.
+    pub(crate) fn maybe_path(self, path: Option<impl AsRef<str>>) -> Builder<impl Cookie> {
         self.and_then(move |c| {
-            if crate::parse::is_valid_path(path.as_ref()) {
-                Ok(util::Delegated(WithPath(c, path)))
-            } else {
-                // When parsing, invalid paths are just ignored. However,
-                // when building a cookie, a user should know when they
-                // set something bad.
-                Err(Error::invalid_path())
+            match path {
+                Some(ref p) if !crate::parse::is_valid_path(p.as_ref()) => {
+                    // When parsing, invalid paths are just ignored. However,
+                    // when building a cookie, a user should know when they
+                    // set something bad.
+                    Err(Error::invalid_path())
+                }
+                _ => Ok(util::Delegated(WithPath(c, path))),
             }
         })
     }
 
     /// Set the `Domain` attribute of this cookie.
     pub fn domain(self, domain: impl AsRef<str>) -> Builder<impl Cookie> {
+        self.maybe_domain(Some(domain))
+    }
+
+    /// Set the `Domain` attribute of this cookie, if there is one.
+    pub(crate) fn maybe_domain(self, domain: Option<impl AsRef<str>>) -> Builder<impl Cookie> {
         // TODO: validate domain
         self.and_then(move |c| {
             use crate::parse::Domain;
-            match crate::parse::validate_domain(domain.as_ref()) {
-                Domain::AsIs => Ok(util::Delegated(WithDomain(c, domain))),
-                Domain::LeadingDot | Domain::Invalid => Err(Error::invalid_domain()),
+            match domain
+                .as_ref()
+                .map(|d| crate::parse::validate_domain(d.as_ref()))
+            {
+                None | Some(Domain::AsIs) => Ok(util::Delegated(WithDomain(c, domain))),
+                Some(Domain::LeadingDot) | Some(Domain::Invalid) => Err(Error::invalid_domain()),
             }
         })
     }
 
     /// Set the `Max-Age` attribute of this cookie.
+    ///
+    /// This replaces any `Expires` of a previous cookie.
     pub fn max_age(self, max_age: Duration) -> Builder<impl Cookie> {
-        self.and_then(move |c| Ok(util::Delegated(WithMaxAge(c, max_age))))
+        let pinned_at = self.pinned_at.clone();
+        self.and_then(move |c| Ok(util::Delegated(WithMaxAge(c, max_age, pinned_at))))
+    }
+
+    /// Set the `Expires` attribute of this cookie to a point in time.
+    pub fn expires(self, expires: SystemTime) -> Builder<impl Cookie> {
+        self.maybe_expires(Some(expires))
+    }
+
+    /// Set the `Expires` attribute of this cookie, if there is one.
+    pub(crate) fn maybe_expires(self, expires: Option<SystemTime>) -> Builder<impl Cookie> {
+        self.and_then(move |c| {
+            let expires = expires.or_else(|| c.expires());
+            Ok(util::Delegated(WithExpires(c, expires)))
+        })
+    }
+
+    /// Pin the `Expires` attribute to an absolute point in time.
+    ///
+    /// By default, `Expires` is calculated from `Max-Age` every time the
+    /// cookie is written, so a cookie sent on several responses will have a
+    /// different `Expires` each time. With this option, `Expires` is fixed to
+    /// the time `build` is called plus the `Max-Age`, and the same instant is
+    /// written every time. It doesn't matter whether `max_age` is called
+    /// before or after this.
+    pub fn pin_expiry(mut self) -> Builder<impl Cookie> {
+        let pinned_at = self.pinned_at.get_or_insert_with(PinnedAt::default).clone();
+        self.and_then(move |c| Ok(util::Delegated(WithPinnedExpiry(c, pinned_at))))
     }
 
     /// Enable or disable the `Secure` attribute of this cookie.
@@ -94,25 +195,77 @@ impl<C: Cookie> Builder<C> {
         self.and_then(move |c| Ok(util::Delegated(WithHttpOnly(c, http_only))))
     }
 
+    /// Set the `SameSite` attribute of this cookie.
+    ///
+    /// Passing `None` removes the attribute.
+    pub fn same_site(self, same_site: impl Into<Option<SameSite>>) -> Builder<impl Cookie> {
+        let same_site = same_site.into();
+        self.and_then(move |c| Ok(util::Delegated(WithSameSite(c, same_site))))
+    }
+
+    /// Set the `Priority` attribute of this cookie.
+    ///
+    /// Passing `None` removes the attribute.
+    pub fn priority(self, priority: impl Into<Option<Priority>>) -> Builder<impl Cookie> {
+        let priority = priority.into();
+        self.and_then(move |c| Ok(util::Delegated(WithPriority(c, priority))))
+    }
+
+    /// Enable or disable the `Partitioned` attribute of this cookie.
+    ///
+    /// User-agents only accept partitioned cookies that are also `Secure`.
+    pub fn partitioned(self, partitioned: bool) -> Builder<impl Cookie> {
+        self.and_then(move |c| Ok(util::Delegated(WithPartitioned(c, partitioned))))
+    }
+
+    /// Limit the length of the `Set-Cookie` string of this cookie.
+    ///
+    /// User-agents and proxies drop cookies that are too long, so `build`
+    /// will return an error instead if the cookie, with all its attributes,
+    /// would be longer than `max` bytes. See `serialized_len`.
+    pub fn max_serialized_len(mut self, max: usize) -> Builder<C> {
+        self.max_len = Some(max);
+        self
+    }
+
     /// Consumes the builder trying to return the constructed `Cookie`.
     ///
     /// # Error
     ///
     /// Returns an error if any of the builder steps were passed an invalid
-    /// value.
+    /// value, or if the cookie is longer than `max_serialized_len`, or too
+    /// long to be parsed again with a `typed_value`.
     pub fn build(self) -> Result<C, Error> {
-        self.state
+        let cookie = self.state?;
+        if let Some(pinned_at) = self.pinned_at {
+            pinned_at.get_or_init(SystemTime::now);
+        }
+        let max = match (self.max_len, self.parseable) {
+            (Some(max), true) => Some(max.min(crate::parse::MAX_LENGTH)),
+            (Some(max), false) => Some(max),
+            (None, true) => Some(crate::parse::MAX_LENGTH),
+            (None, false) => None,
+        };
+        if let Some(max) = max {
+            if util::serialized_len(&cookie) > max {
+                return Err(Error::too_long());
+            }
+        }
+        Ok(cookie)
     }
 
     // private
 
-    fn and_then<F, R>(self, func: F) -> Builder<impl Cookie>
+    pub(crate) fn and_then<F, R>(self, func: F) -> Builder<impl Cookie>
     where
         F: FnOnce(C) -> Result<R, Error>,
         R: Cookie,
     {
         Builder {
             state: self.state.and_then(func),
+            max_len: self.max_len,
+            parseable: self.parseable,
+            pinned_at: self.pinned_at,
         }
     }
 }
@@ -140,6 +293,10 @@ impl<N: AsRef<str>, V: AsRef<str>> Cookie for Pair<N, V> {
         None
     }
 
+    fn expires(&self) -> Option<SystemTime> {
+        None
+    }
+
     fn http_only(&self) -> bool {
         false
     }
@@ -151,6 +308,14 @@ impl<N: AsRef<str>, V: AsRef<str>> Cookie for Pair<N, V> {
     fn same_site(&self) -> Option<SameSite> {
         None
     }
+
+    fn priority(&self) -> Option<Priority> {
+        None
+    }
+
+    fn partitioned(&self) -> bool {
+        false
+    }
 }
 
 impl<N, V> Sealed for Pair<N, V> {}
@@ -189,7 +354,10 @@ impl<C: Cookie, P: AsRef<str>> util::Delegate for WithPath<C, P> {
     }
 
     fn path(&self) -> Option<&str> {
-        Some(self.1.as_ref())
+        match self.1 {
+            Some(ref path) => Some(path.as_ref()),
+            None => self.0.path(),
+        }
     }
 }
 
@@ -202,7 +370,10 @@ impl<C: Cookie, D: AsRef<str>> util::Delegate for WithDomain<C, D> {
     }
 
     fn domain(&self) -> Option<&str> {
-        Some(self.1.as_ref())
+        match self.1 {
+            Some(ref domain) => Some(domain.as_ref()),
+            None => self.0.domain(),
+        }
     }
 }
 
@@ -217,6 +388,40 @@ impl<C: Cookie> util::Delegate for WithMaxAge<C> {
     fn max_age(&self) -> Option<Duration> {
         Some(self.1)
     }
+
+    fn expires(&self) -> Option<SystemTime> {
+        let at = self.2.as_ref()?.get()?;
+        Some(util::saturating_add(*at, self.1))
+    }
+}
+
+// ===== impl WithExpires =====
+
+impl<C: Cookie> util::Delegate for WithExpires<C> {
+    type Cookie = C;
+    fn cookie(&self) -> &Self::Cookie {
+        &self.0
+    }
+
+    fn expires(&self) -> Option<SystemTime> {
+        self.1
+    }
+}
+
+// ===== impl WithPinnedExpiry =====
+
+impl<C: Cookie> util::Delegate for WithPinnedExpiry<C> {
+    type Cookie = C;
+    fn cookie(&self) -> &Self::Cookie {
+        &self.0
+    }
+
+    fn expires(&self) -> Option<SystemTime> {
+        match (self.1.get(), self.0.max_age()) {
+            (Some(at), Some(max_age)) => Some(util::saturating_add(*at, max_age)),
+            _ => self.0.expires(),
+        }
+    }
 }
 
 // ===== impl WithSecure =====
@@ -245,6 +450,45 @@ impl<C: Cookie> util::Delegate for WithHttpOnly<C> {
     }
 }
 
+// ===== impl WithSameSite =====
+
+impl<C: Cookie> util::Delegate for WithSameSite<C> {
+    type Cookie = C;
+    fn cookie(&self) -> &Self::Cookie {
+        &self.0
+    }
+
+    fn same_site(&self) -> Option<SameSite> {
+        self.1
+    }
+}
+
+// ===== impl WithPriority =====
+
+impl<C: Cookie> util::Delegate for WithPriority<C> {
+    type Cookie = C;
+    fn cookie(&self) -> &Self::Cookie {
+        &self.0
+    }
+
+    fn priority(&self) -> Option<Priority> {
+        self.1
+    }
+}
+
+// ===== impl WithPartitioned =====
+
+impl<C: Cookie> util::Delegate for WithPartitioned<C> {
+    type Cookie = C;
+    fn cookie(&self) -> &Self::Cookie {
+        &self.0
+    }
+
+    fn partitioned(&self) -> bool {
+        self.1
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -282,6 +526,67 @@ mod tests {
         assert_eq!(c.value(), "wat");
     }
 
+    #[test]
+    fn with_encoded_value() {
+        use crate::codec;
+
+        let c = Builder::new("foo", "")
+            .encoded_value(codec::Base64Url, b"\x00\xff")
+            .build()
+            .unwrap();
+
+        assert_eq!(c.value(), "AP8");
+        assert_eq!(
+            c.decoded_value(&codec::Base64Url).unwrap(),
+            &b"\x00\xff"[..]
+        );
+
+        Builder::new("foo", "")
+            .encoded_value(codec::Identity, "a b")
+            .build()
+            .expect_err("identity doesn't escape spaces");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn with_typed_value() {
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Prefs {
+            theme: String,
+            font_size: u8,
+        }
+
+        let prefs = Prefs {
+            theme: "dark; really".into(),
+            font_size: 14,
+        };
+
+        let c = Builder::new("prefs", "")
+            .typed_value(&prefs)
+            .build()
+            .unwrap();
+
+        assert_eq!(c.typed_value::<Prefs>().unwrap(), prefs);
+
+        let big = "x".repeat(crate::parse::MAX_LENGTH);
+        Builder::new("prefs", "")
+            .typed_value(&big)
+            .build()
+            .expect_err("too long");
+
+        // The value fits on its own, but not with the attributes.
+        let fits = "x".repeat(3000);
+        Builder::new("prefs", "")
+            .typed_value(&fits)
+            .build()
+            .expect("short enough");
+        Builder::new("prefs", "")
+            .typed_value(&fits)
+            .path(format!("/{}", "p".repeat(200)))
+            .build()
+            .expect_err("too long with Path");
+    }
+
     #[test]
     fn with_path() {
         let c = Builder::new("foo", "bar").path("/hallo").build().unwrap();
@@ -331,5 +636,110 @@ mod tests {
             .unwrap();
 
         assert_eq!(c.max_age(), Some(Duration::from_secs(10)));
+        assert_eq!(c.expires(), None);
+    }
+
+    #[test]
+    fn with_expires() {
+        let at = SystemTime::UNIX_EPOCH + Duration::from_secs(1_558_473_131);
+        let c = Builder::new("foo", "bar").expires(at).build().unwrap();
+
+        assert_eq!(c.expires(), Some(at));
+        assert_eq!(c.max_age(), None);
+        assert_eq!(
+            c.to_string(),
+            "foo=bar; Expires=Tue, 21 May 2019 21:12:11 GMT"
+        );
+    }
+
+    #[test]
+    fn with_pinned_expiry() {
+        let before = SystemTime::now();
+        let c = Builder::new("foo", "bar")
+            .max_age(Duration::from_secs(10))
+            .pin_expiry()
+            .build()
+            .unwrap();
+
+        let expires = c.expires().expect("pinned expires");
+        assert!(expires >= before + Duration::from_secs(10));
+        assert_eq!(c.max_age(), Some(Duration::from_secs(10)));
+        assert_eq!(c.to_string(), c.to_string());
+
+        // the clock is read in `build`, not in `pin_expiry`
+        let builder = Builder::new("foo", "bar")
+            .max_age(Duration::from_secs(10))
+            .pin_expiry();
+        std::thread::sleep(Duration::from_millis(20));
+        let built_at = SystemTime::now();
+        let c = builder.build().unwrap();
+        assert!(c.expires().unwrap() >= built_at + Duration::from_secs(10));
+
+        // pinning before setting Max-Age works too
+        let c = Builder::new("foo", "bar")
+            .pin_expiry()
+            .max_age(Duration::from_secs(10))
+            .build()
+            .unwrap();
+
+        let expires2 = c.expires().expect("pinned expires");
+        assert!(expires2 >= before + Duration::from_secs(10));
+
+        // a huge Max-Age saturates instead of overflowing
+        let huge = Duration::from_secs(u64::MAX);
+        let c = Builder::new("foo", "bar")
+            .max_age(huge)
+            .pin_expiry()
+            .build()
+            .unwrap();
+        let c2 = Builder::new("foo", "bar")
+            .pin_expiry()
+            .max_age(huge)
+            .build()
+            .unwrap();
+        assert_eq!(c.expires(), c2.expires());
+        assert!(c
+            .to_string()
+            .ends_with("Expires=Fri, 31 Dec 9999 23:59:59 GMT"));
+    }
+
+    #[test]
+    fn with_same_site() {
+        let c = Builder::new("foo", "bar")
+            .same_site(SameSite::LAX)
+            .build()
+            .unwrap();
+
+        assert_eq!(c.same_site(), Some(SameSite::LAX));
+
+        let c2 = Builder::wrap(c).same_site(None).build().unwrap();
+
+        assert_eq!(c2.same_site(), None);
+    }
+
+    #[test]
+    fn with_priority() {
+        let c = Builder::new("foo", "bar")
+            .priority(Priority::HIGH)
+            .build()
+            .unwrap();
+
+        assert_eq!(c.priority(), Some(Priority::HIGH));
+        assert_eq!(c.to_string(), "foo=bar; Priority=High");
+    }
+
+    #[test]
+    fn max_serialized_len() {
+        Builder::new("foo", "bar")
+            .max_serialized_len(15)
+            .path("/")
+            .build()
+            .expect("exactly max len");
+
+        Builder::new("foo", "bar")
+            .max_serialized_len(14)
+            .path("/")
+            .build()
+            .expect_err("longer than max len");
     }
 }
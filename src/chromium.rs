@@ -0,0 +1,342 @@
+//! Import cookies from a Chromium profile on Linux.
+//!
+//! Chromium stores its cookies in the `cookies` table of the `Cookies` file
+//! in the profile directory. Values are either plaintext, or encrypted:
+//!
+//! - `v10` values are encrypted with a key derived from the fixed password
+//!   `peanuts`, used when no keyring is available. These are decrypted.
+//! - `v11` values are encrypted with a password from the desktop keyring,
+//!   such as GNOME Keyring or KWallet. These are unsupported.
+//!
+//! Requires the `chromium` feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use cookies::{chromium, CookieJar};
+//!
+//! let mut jar = CookieJar::new();
+//! for cookie in chromium::read("/path/to/profile/Cookies").unwrap() {
+//!     match cookie {
+//!         Ok(cookie) => {
+//!             // Cookies no site could set, such as for `com`, are rejected.
+//!             let _ = jar.insert(cookie);
+//!         }
+//!         Err(err) => eprintln!("skipped: {}", err),
+//!     }
+//! }
+//! ```
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use aes::cipher::block_padding::Pkcs7;
+use aes::cipher::{BlockDecryptMut, KeyIvInit};
+
+use crate::parse::{self, Domain};
+use crate::sqlite::{self, OpenError};
+use crate::{Priority, SameSite, StoredCookie};
+
+type Aes128CbcDec = cbc::Decryptor<aes::Aes128>;
+
+/// An error reading a `Cookies` file, or one of its cookies.
+#[derive(Debug)]
+pub struct Error {
+    kind: Kind,
+    // The host and name of the cookie, for an error with a single cookie.
+    cookie: Option<(String, String)>,
+}
+
+#[derive(Debug)]
+enum Kind {
+    Io(io::Error),
+    Sqlite(rusqlite::Error),
+    Unsupported(&'static str),
+    Decrypt,
+    Invalid(crate::Error),
+}
+
+// Since this version, the decrypted value starts with the SHA-256 of the
+// host key.
+const HOST_DIGEST_VERSION: i64 = 24;
+const HOST_DIGEST_LEN: usize = 32;
+
+// Microseconds between 1601-01-01, the Windows epoch, and the Unix epoch.
+const WINDOWS_EPOCH_OFFSET: i64 = 11_644_473_600_000_000;
+
+/// Read all the cookies of a Chromium `Cookies` file.
+///
+/// The file is opened read-only, and can be read while Chromium is running,
+/// though cookies it hasn't written yet will be missing.
+///
+/// Each cookie is either imported, or an error if it can't be, such as when
+/// its value is encrypted with a keyring password (see `Error::is_unsupported`).
+/// A cookie with a top-frame site is imported as partitioned.
+///
+/// # Error
+///
+/// Returns an error if the file can't be opened, or doesn't have a
+/// `cookies` table.
+pub fn read(path: impl AsRef<Path>) -> Result<Vec<Result<StoredCookie, Error>>, Error> {
+    let conn = sqlite::open(path.as_ref())?;
+
+    let version = conn
+        .query_row("SELECT value FROM meta WHERE key = 'version'", [], |row| {
+            row.get::<_, String>(0)
+        })
+        .ok()
+        .and_then(|version| version.parse::<i64>().ok())
+        .unwrap_or(0);
+
+    let mut stmt = conn.prepare(
+        "SELECT host_key, top_frame_site_key, name, value, encrypted_value, path, \
+                expires_utc, has_expires, is_persistent, creation_utc, last_access_utc, \
+                is_secure, is_httponly, samesite, priority \
+         FROM cookies",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(Row {
+            host: row.get(0)?,
+            top_frame_site: row.get(1)?,
+            name: row.get(2)?,
+            value: row.get(3)?,
+            encrypted_value: row.get(4)?,
+            path: row.get(5)?,
+            expires: row.get(6)?,
+            has_expires: row.get(7)?,
+            persistent: row.get(8)?,
+            creation: row.get(9)?,
+            last_access: row.get(10)?,
+            secure: row.get(11)?,
+            http_only: row.get(12)?,
+            same_site: row.get(13)?,
+            priority: row.get(14)?,
+        })
+    })?;
+
+    let key = key();
+    let mut cookies = Vec::new();
+    for row in rows {
+        let row = row?;
+        let cookie = row.clone_id();
+        cookies.push(row.into_cookie(&key, version).map_err(|kind| Error {
+            kind,
+            cookie: Some(cookie),
+        }));
+    }
+    Ok(cookies)
+}
+
+/// The key of `v10` values, derived from the fixed password.
+fn key() -> [u8; 16] {
+    let mut key = [0; 16];
+    pbkdf2::pbkdf2_hmac::<sha1::Sha1>(b"peanuts", b"saltysalt", 1, &mut key);
+    key
+}
+
+fn decrypt(key: &[u8; 16], encrypted: &[u8], version: i64) -> Result<String, Kind> {
+    let ciphertext = match encrypted.get(..3) {
+        Some(b"v10") => &encrypted[3..],
+        Some(b"v11") => return Err(Kind::Unsupported("v11 values encrypted with a keyring")),
+        _ => return Err(Kind::Unsupported("unknown value encryption")),
+    };
+
+    let mut plain = Aes128CbcDec::new(key.into(), &[b' '; 16].into())
+        .decrypt_padded_vec_mut::<Pkcs7>(ciphertext)
+        .map_err(|_| Kind::Decrypt)?;
+    if version >= HOST_DIGEST_VERSION {
+        if plain.len() < HOST_DIGEST_LEN {
+            return Err(Kind::Decrypt);
+        }
+        plain.drain(..HOST_DIGEST_LEN);
+    }
+    String::from_utf8(plain).map_err(|_| Kind::Decrypt)
+}
+
+fn from_windows_micros(micros: i64) -> SystemTime {
+    let unix = micros.saturating_sub(WINDOWS_EPOCH_OFFSET);
+    UNIX_EPOCH + Duration::from_micros(unix.max(0) as u64)
+}
+
+struct Row {
+    host: String,
+    top_frame_site: String,
+    name: String,
+    value: String,
+    encrypted_value: Vec<u8>,
+    path: String,
+    expires: i64,
+    has_expires: bool,
+    persistent: bool,
+    creation: i64,
+    last_access: i64,
+    secure: bool,
+    http_only: bool,
+    same_site: i64,
+    priority: i64,
+}
+
+impl Row {
+    fn clone_id(&self) -> (String, String) {
+        (self.host.clone(), self.name.clone())
+    }
+
+    fn into_cookie(self, key: &[u8; 16], version: i64) -> Result<StoredCookie, Kind> {
+        let value = if !self.value.is_empty() || self.encrypted_value.is_empty() {
+            self.value
+        } else {
+            decrypt(key, &self.encrypted_value, version)?
+        };
+
+        parse::validate_name(&self.name).map_err(Kind::Invalid)?;
+        parse::validate_value(&value).map_err(Kind::Invalid)?;
+        if !parse::is_valid_path(&self.path) {
+            return Err(Kind::Invalid(crate::Error::invalid_path()));
+        }
+        let (domain, host_only) = match parse::validate_domain(&self.host) {
+            Domain::AsIs => (&self.host[..], true),
+            Domain::LeadingDot => (&self.host[1..], false),
+            Domain::Invalid => return Err(Kind::Invalid(crate::Error::invalid_domain())),
+        };
+
+        let mut cookie =
+            StoredCookie::new(self.name, value, domain.to_ascii_lowercase(), self.path);
+        if self.has_expires && self.persistent {
+            cookie.expiry = Some(from_windows_micros(self.expires));
+        }
+        cookie.creation = from_windows_micros(self.creation);
+        cookie
+            .last_access
+            .set(from_windows_micros(self.last_access));
+        cookie.host_only = host_only;
+        cookie.secure = self.secure;
+        cookie.http_only = self.http_only;
+        cookie.same_site = match self.same_site {
+            0 => Some(SameSite::NONE),
+            1 => Some(SameSite::LAX),
+            2 => Some(SameSite::STRICT),
+            _ => None,
+        };
+        // Medium is the default, which every cookie without a `Priority`
+        // is stored with.
+        cookie.priority = match self.priority {
+            0 => Some(Priority::LOW),
+            2 => Some(Priority::HIGH),
+            _ => None,
+        };
+        if !self.top_frame_site.is_empty() {
+            cookie.partition_key = Some(self.top_frame_site);
+        }
+        Ok(cookie)
+    }
+}
+
+// ===== impl Error =====
+
+impl Error {
+    /// Check if this error is for a cookie whose value is encrypted in a
+    /// way that isn't supported, such as with a keyring password.
+    pub fn is_unsupported(&self) -> bool {
+        matches!(self.kind, Kind::Unsupported(_))
+    }
+}
+
+impl From<OpenError> for Error {
+    fn from(err: OpenError) -> Error {
+        let kind = match err {
+            OpenError::Io(err) => Kind::Io(err),
+            OpenError::Sqlite(err) => Kind::Sqlite(err),
+        };
+        Error { kind, cookie: None }
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(err: rusqlite::Error) -> Error {
+        Error {
+            kind: Kind::Sqlite(err),
+            cookie: None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("reading Chromium cookies: ")?;
+        if let Some((ref host, ref name)) = self.cookie {
+            write!(f, "cookie {:?} for {:?}: ", name, host)?;
+        }
+        match self.kind {
+            Kind::Io(ref err) => fmt::Display::fmt(err, f),
+            Kind::Sqlite(ref err) => fmt::Display::fmt(err, f),
+            Kind::Unsupported(what) => write!(f, "{} are unsupported", what),
+            Kind::Decrypt => f.write_str("value failed to decrypt"),
+            Kind::Invalid(ref err) => fmt::Display::fmt(err, f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self.kind {
+            Kind::Io(ref err) => Some(err),
+            Kind::Sqlite(ref err) => Some(err),
+            Kind::Invalid(ref err) => Some(err),
+            Kind::Unsupported(_) | Kind::Decrypt => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aes::cipher::BlockEncryptMut;
+
+    use super::*;
+
+    fn encrypt(plain: &[u8]) -> Vec<u8> {
+        let ciphertext = cbc::Encryptor::<aes::Aes128>::new(&key().into(), &[b' '; 16].into())
+            .encrypt_padded_vec_mut::<Pkcs7>(plain);
+        [&b"v10"[..], &ciphertext].concat()
+    }
+
+    #[test]
+    fn known_key() {
+        assert_eq!(
+            key(),
+            [
+                0xfd, 0x62, 0x1f, 0xe5, 0xa2, 0xb4, 0x02, 0x53, 0x9d, 0xfa, 0x14, 0x7c, 0xa9, 0x27,
+                0x27, 0x78
+            ]
+        );
+    }
+
+    #[test]
+    fn decrypt_v10() {
+        let key = key();
+        assert_eq!(decrypt(&key, &encrypt(b"abc"), 23).unwrap(), "abc");
+
+        let with_digest = [&[0; HOST_DIGEST_LEN][..], b"abc"].concat();
+        assert_eq!(decrypt(&key, &encrypt(&with_digest), 24).unwrap(), "abc");
+
+        let mut tampered = encrypt(b"abc");
+        tampered[3] ^= 1;
+        assert!(decrypt(&key, &tampered, 23).is_err());
+    }
+
+    #[test]
+    fn unsupported() {
+        let key = key();
+        let err = decrypt(&key, b"v11\0\0\0", 24).unwrap_err();
+        assert!(matches!(err, Kind::Unsupported(_)));
+        let err = decrypt(&key, b"\x01\x00\x00\x00", 24).unwrap_err();
+        assert!(matches!(err, Kind::Unsupported(_)));
+    }
+
+    #[test]
+    fn windows_epoch() {
+        assert_eq!(from_windows_micros(WINDOWS_EPOCH_OFFSET), UNIX_EPOCH);
+        assert_eq!(from_windows_micros(0), UNIX_EPOCH);
+    }
+}
@@ -0,0 +1,330 @@
+//! Split large cookie values across multiple cookies.
+//!
+//! User-agents drop cookies longer than about 4096 bytes. A value that is
+//! too large can instead be split into chunks named `name.0`, `name.1`, and
+//! so on, which share the attributes of the original cookie. The chunks are
+//! joined back together from the `Cookie` request header.
+//!
+//! # Example
+//!
+//! ```
+//! use cookies::{chunk, Builder, Cookie};
+//!
+//! let token = "a".repeat(10);
+//! let c = Builder::new("token", &token).path("/").build().unwrap();
+//!
+//! let set_cookies = chunk::split(&c, 4)
+//!     .map(|chunk| chunk.to_string())
+//!     .collect::<Vec<_>>();
+//!
+//! assert_eq!(set_cookies, [
+//!     "token.0=aaaa; Path=/",
+//!     "token.1=aaaa; Path=/",
+//!     "token.2=aa; Path=/",
+//! ]);
+//!
+//! // The user-agent sends them back...
+//! let header = "token.0=aaaa; token.1=aaaa; token.2=aa";
+//!
+//! assert_eq!(chunk::join(header, "token").unwrap(), token);
+//! ```
+
+use std::borrow::Cow;
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use super::{Cookie, Priority, SameSite, Sealed};
+use crate::util;
+
+/// A single chunk of a split `Cookie`.
+pub struct Chunk<'a, C> {
+    cookie: &'a C,
+    name: Cow<'a, str>,
+    value: &'a str,
+    removal: bool,
+}
+
+/// An iterator over the chunks of a `Cookie`, created by `split`.
+#[derive(Debug)]
+pub struct Split<'a, C> {
+    cookie: &'a C,
+    max_len: usize,
+    pos: usize,
+    index: usize,
+    total: usize,
+}
+
+/// Split a `Cookie` into chunks whose values are at most `max_len` bytes.
+///
+/// If the value already fits, a single chunk with the original name is
+/// yielded. Otherwise, the chunks are named `name.0`, `name.1`, etc.
+///
+/// # Panics
+///
+/// Panics if `max_len` is 0.
+pub fn split<C: Cookie>(cookie: &C, max_len: usize) -> Split<'_, C> {
+    assert!(max_len > 0, "chunk max_len must be greater than 0");
+    let len = cookie.value().len();
+    Split {
+        cookie,
+        max_len,
+        pos: 0,
+        index: 0,
+        total: if len == 0 { 1 } else { len.div_ceil(max_len) },
+    }
+}
+
+/// Join the chunks of the cookie `name` from a `Cookie` request header.
+///
+/// If the header contains a cookie with exactly `name`, its value is
+/// returned as-is. Otherwise, the values of `name.0`, `name.1`, ... are
+/// concatenated.
+///
+/// Returns `None` if neither `name` nor any chunk is in the header, or if
+/// a chunk is missing or repeated, since the value would be wrong.
+pub fn join<'a>(header: &'a str, name: &str) -> Option<Cow<'a, str>> {
+    let mut chunks = Vec::new();
+    for (n, v) in crate::parse_pairs(header) {
+        if n == name {
+            return Some(Cow::Borrowed(v));
+        }
+        if let Some(index) = chunk_index(n, name) {
+            chunks.push((index, v));
+        }
+    }
+
+    chunks.sort_by_key(|&(index, _)| index);
+
+    if chunks.is_empty() {
+        return None;
+    }
+
+    let mut joined = String::new();
+    for (expected, &(index, value)) in chunks.iter().enumerate() {
+        if index != expected {
+            return None;
+        }
+        joined.push_str(value);
+    }
+    Some(Cow::Owned(joined))
+}
+
+/// Create removal cookies for chunks in a request header that are stale.
+///
+/// After splitting a new value into `count` chunks (see `Split::len`), any
+/// chunks of a previous value the user-agent still has should be removed.
+/// This looks at the `Cookie` request `header`, and returns a chunk with an
+/// empty value and a `Max-Age` of 0 for each stale one, with the same
+/// attributes as `cookie`.
+pub fn removals<'a, C: Cookie>(cookie: &'a C, header: &str, count: usize) -> Vec<Chunk<'a, C>> {
+    let name = cookie.name();
+    let mut removals = Vec::new();
+    for (n, _) in crate::parse_pairs(header) {
+        let stale = if n == name {
+            // The plain name is only used when there's a single chunk.
+            count > 1
+        } else if let Some(index) = chunk_index(n, name) {
+            count <= 1 || index >= count
+        } else {
+            false
+        };
+
+        if stale {
+            removals.push(Chunk {
+                cookie,
+                name: Cow::Owned(n.to_owned()),
+                value: "",
+                removal: true,
+            });
+        }
+    }
+    removals
+}
+
+fn chunk_index(chunk_name: &str, name: &str) -> Option<usize> {
+    let suffix = chunk_name.strip_prefix(name)?.strip_prefix('.')?;
+    if suffix.is_empty() || !suffix.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    suffix.parse().ok()
+}
+
+// ===== impl Split =====
+
+impl<'a, C: Cookie> Iterator for Split<'a, C> {
+    type Item = Chunk<'a, C>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.total {
+            return None;
+        }
+
+        let cookie = self.cookie;
+        let value = cookie.value();
+        let end = std::cmp::min(self.pos + self.max_len, value.len());
+        let name = if self.total == 1 {
+            Cow::Borrowed(cookie.name())
+        } else {
+            Cow::Owned(format!("{}.{}", cookie.name(), self.index))
+        };
+
+        let chunk = Chunk {
+            cookie,
+            name,
+            value: &value[self.pos..end],
+            removal: false,
+        };
+
+        self.pos = end;
+        self.index += 1;
+        Some(chunk)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.total - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a, C: Cookie> ExactSizeIterator for Split<'a, C> {}
+
+// ===== impl Chunk =====
+
+impl<'a, C: Cookie> Cookie for Chunk<'a, C> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn value(&self) -> &str {
+        self.value
+    }
+
+    fn domain(&self) -> Option<&str> {
+        self.cookie.domain()
+    }
+
+    fn path(&self) -> Option<&str> {
+        self.cookie.path()
+    }
+
+    fn max_age(&self) -> Option<Duration> {
+        if self.removal {
+            Some(Duration::from_secs(0))
+        } else {
+            self.cookie.max_age()
+        }
+    }
+
+    fn expires(&self) -> Option<SystemTime> {
+        if self.removal {
+            None
+        } else {
+            self.cookie.expires()
+        }
+    }
+
+    fn http_only(&self) -> bool {
+        self.cookie.http_only()
+    }
+
+    fn secure(&self) -> bool {
+        self.cookie.secure()
+    }
+
+    fn same_site(&self) -> Option<SameSite> {
+        self.cookie.same_site()
+    }
+
+    fn priority(&self) -> Option<Priority> {
+        self.cookie.priority()
+    }
+
+    fn partitioned(&self) -> bool {
+        self.cookie.partitioned()
+    }
+}
+
+impl<'a, C> Sealed for Chunk<'a, C> {}
+
+impl<'a, C: Cookie> fmt::Debug for Chunk<'a, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        util::debug(self, f)
+    }
+}
+
+impl<'a, C: Cookie> fmt::Display for Chunk<'a, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        util::display(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Builder;
+
+    #[test]
+    fn split_fits() {
+        let c = Builder::new("foo", "bar").build().unwrap();
+        let chunks = split(&c, 3).collect::<Vec<_>>();
+
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].name(), "foo");
+        assert_eq!(chunks[0].value(), "bar");
+    }
+
+    #[test]
+    fn split_keeps_attributes() {
+        let c = Builder::new("foo", "abcdefg")
+            .domain("hyper.rs")
+            .secure(true)
+            .build()
+            .unwrap();
+        let chunks = split(&c, 3);
+
+        assert_eq!(chunks.len(), 3);
+        for (i, chunk) in chunks.enumerate() {
+            assert_eq!(chunk.name(), format!("foo.{}", i));
+            assert_eq!(chunk.domain(), Some("hyper.rs"));
+            assert!(chunk.secure());
+        }
+    }
+
+    #[test]
+    fn join_chunks() {
+        assert_eq!(join("a=1; foo.1=def; foo.0=abc", "foo").unwrap(), "abcdef");
+        assert_eq!(join("foo.0=abc; foo.2=ghi", "foo"), None, "gap");
+        assert_eq!(
+            join("foo.0=abc; foo.1=def; foo.0=xyz", "foo"),
+            None,
+            "duplicate"
+        );
+        assert_eq!(
+            join("foo=bar; foo.0=abc", "foo").unwrap(),
+            "bar",
+            "unchunked"
+        );
+        assert_eq!(
+            join("foo.1=abc; foobar.0=def", "foo"),
+            None,
+            "no first chunk"
+        );
+        assert_eq!(join("foo.x=abc", "foo"), None, "not a chunk index");
+    }
+
+    #[test]
+    fn removal_of_stale_chunks() {
+        let c = Builder::new("foo", "abcdef").path("/").build().unwrap();
+        let count = split(&c, 3).len();
+        let header = "foo=old; foo.0=a; foo.1=b; foo.2=c; other=1";
+
+        let removals = removals(&c, header, count)
+            .iter()
+            .map(|chunk| chunk.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(removals.len(), 2);
+        assert!(removals[0].starts_with("foo=; Path=/; Max-Age=0;"));
+        assert!(removals[1].starts_with("foo.2=; Path=/; Max-Age=0;"));
+    }
+}
@@ -0,0 +1,73 @@
+use http::header::HeaderValue;
+#[cfg(any(feature = "tower", feature = "ureq"))]
+use http::header::{HeaderMap, COOKIE};
+use url::Url;
+
+#[cfg(any(feature = "tower", feature = "ureq"))]
+use crate::RequestContext;
+use crate::SharedCookieJar;
+
+/// Add the cookies of a jar for a URL to the `Cookie` header of a request.
+///
+/// `Cookie` headers already set are joined into one, with the cookies of
+/// the jar appended. If any of them isn't ASCII, the request is left as is.
+#[cfg(any(feature = "tower", feature = "ureq"))]
+pub(crate) fn add_cookies(jar: &SharedCookieJar, url: &Url, headers: &mut HeaderMap) {
+    let mut values = Vec::new();
+    for value in headers.get_all(COOKIE) {
+        match value.to_str() {
+            Ok(value) => values.push(value.to_owned()),
+            Err(_) => return,
+        }
+    }
+    match jar.cookie_header(url, &RequestContext::new()) {
+        Some(header) => values.push(header),
+        None => return,
+    }
+    if let Ok(value) = HeaderValue::from_str(&values.join("; ")) {
+        headers.insert(COOKIE, value);
+    }
+}
+
+/// Store the cookies of `Set-Cookie` header values received from a URL.
+///
+/// Values that aren't ASCII are skipped.
+pub(crate) fn store_cookies<'a>(
+    jar: &SharedCookieJar,
+    url: &Url,
+    values: impl Iterator<Item = &'a HeaderValue>,
+) {
+    jar.store_set_cookies(url, values.filter_map(|value| value.to_str().ok()));
+}
+
+#[cfg(all(test, any(feature = "tower", feature = "ureq")))]
+mod tests {
+    use http::header::SET_COOKIE;
+
+    use super::*;
+
+    #[test]
+    fn add_and_store_cookies() {
+        let jar = SharedCookieJar::new();
+        let url = Url::parse("http://hyper.rs/").unwrap();
+
+        let mut received = HeaderMap::new();
+        received.append(SET_COOKIE, HeaderValue::from_static("a=1"));
+        received.append(SET_COOKIE, HeaderValue::from_bytes(b"b=\xff").unwrap());
+        store_cookies(&jar, &url, received.get_all(SET_COOKIE).iter());
+        assert_eq!(jar.read().len(), 1, "non-ASCII skipped");
+
+        let mut headers = HeaderMap::new();
+        headers.append(COOKIE, HeaderValue::from_static("b=2"));
+        headers.append(COOKIE, HeaderValue::from_static("c=3"));
+        add_cookies(&jar, &url, &mut headers);
+        let values = headers.get_all(COOKIE).iter().collect::<Vec<_>>();
+        assert_eq!(values, ["b=2; c=3; a=1"]);
+
+        let mut headers = HeaderMap::new();
+        headers.append(COOKIE, HeaderValue::from_static("b=2"));
+        headers.append(COOKIE, HeaderValue::from_bytes(b"c=\xff").unwrap());
+        add_cookies(&jar, &url, &mut headers);
+        assert_eq!(headers.get_all(COOKIE).iter().count(), 2, "left as is");
+    }
+}
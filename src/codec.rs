@@ -0,0 +1,258 @@
+//! Codecs for cookie values.
+//!
+//! A cookie value may only contain a limited set of US-ASCII characters
+//! (no whitespace, commas, semicolons, backslashes or double quotes). A
+//! `Codec` maps arbitrary bytes to a valid value and back, so the same
+//! encoding can be used when building and when reading a cookie.
+//!
+//! # Example
+//!
+//! ```
+//! use cookies::{codec, Builder, Cookie};
+//!
+//! let c = Builder::new("greeting", "")
+//!     .encoded_value(codec::Percent, "hello, world")
+//!     .build()
+//!     .expect("valid cookie");
+//!
+//! assert_eq!(c.value(), "hello%2C%20world");
+//! assert_eq!(c.decoded_str(&codec::Percent).unwrap(), "hello, world");
+//! ```
+
+use std::borrow::Cow;
+
+use crate::Error;
+
+/// Encodes bytes into a cookie value, and decodes them back.
+pub trait Codec {
+    /// Encode some raw bytes as a cookie value.
+    fn encode<'a>(&self, raw: &'a [u8]) -> Cow<'a, str>;
+
+    /// Decode a cookie value back into the raw bytes.
+    ///
+    /// # Error
+    ///
+    /// Returns an error if the value isn't valid for this codec.
+    fn decode<'a>(&self, value: &'a str) -> Result<Cow<'a, [u8]>, Error>;
+}
+
+/// A `Codec` that leaves values untouched.
+///
+/// Encoding bytes that aren't valid cookie characters results in an invalid
+/// value, which a `Builder` will reject.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Identity;
+
+/// A `Codec` using the percent-encoding of RFC 3986.
+///
+/// Every byte except the unreserved characters (`A-Z`, `a-z`, `0-9`, `-`,
+/// `.`, `_` and `~`) is encoded as `%XX`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Percent;
+
+/// A `Codec` using the URL-safe base64 alphabet of RFC 4648, without
+/// padding.
+///
+/// Padding is accepted when decoding.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Base64Url;
+
+// ===== impl Codec =====
+
+impl<C: Codec + ?Sized> Codec for &C {
+    fn encode<'a>(&self, raw: &'a [u8]) -> Cow<'a, str> {
+        (**self).encode(raw)
+    }
+
+    fn decode<'a>(&self, value: &'a str) -> Result<Cow<'a, [u8]>, Error> {
+        (**self).decode(value)
+    }
+}
+
+// ===== impl Identity =====
+
+impl Codec for Identity {
+    fn encode<'a>(&self, raw: &'a [u8]) -> Cow<'a, str> {
+        String::from_utf8_lossy(raw)
+    }
+
+    fn decode<'a>(&self, value: &'a str) -> Result<Cow<'a, [u8]>, Error> {
+        Ok(Cow::Borrowed(value.as_bytes()))
+    }
+}
+
+// ===== impl Percent =====
+
+impl Codec for Percent {
+    fn encode<'a>(&self, raw: &'a [u8]) -> Cow<'a, str> {
+        if raw.iter().all(|&b| is_unreserved(b)) {
+            // All unreserved bytes are ASCII, so this can't fail.
+            return String::from_utf8_lossy(raw);
+        }
+
+        let mut dst = String::with_capacity(raw.len() * 3);
+        for &byte in raw {
+            if is_unreserved(byte) {
+                dst.push(byte as char);
+            } else {
+                dst.push('%');
+                dst.push(HEX[(byte >> 4) as usize] as char);
+                dst.push(HEX[(byte & 0x0F) as usize] as char);
+            }
+        }
+        Cow::Owned(dst)
+    }
+
+    fn decode<'a>(&self, value: &'a str) -> Result<Cow<'a, [u8]>, Error> {
+        let src = value.as_bytes();
+        if !src.contains(&b'%') {
+            return Ok(Cow::Borrowed(src));
+        }
+
+        let mut dst = Vec::with_capacity(src.len());
+        let mut i = 0;
+        while i < src.len() {
+            if src[i] == b'%' {
+                let hi = src.get(i + 1).and_then(|&b| from_hex(b));
+                let lo = src.get(i + 2).and_then(|&b| from_hex(b));
+                match (hi, lo) {
+                    (Some(hi), Some(lo)) => dst.push(hi << 4 | lo),
+                    _ => return Err(Error::invalid_value()),
+                }
+                i += 3;
+            } else {
+                dst.push(src[i]);
+                i += 1;
+            }
+        }
+        Ok(Cow::Owned(dst))
+    }
+}
+
+const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+fn is_unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'.' || byte == b'_' || byte == b'~'
+}
+
+fn from_hex(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
+
+// ===== impl Base64Url =====
+
+impl Codec for Base64Url {
+    fn encode<'a>(&self, raw: &'a [u8]) -> Cow<'a, str> {
+        let mut dst = String::with_capacity(raw.len().div_ceil(3) * 4);
+        for chunk in raw.chunks(3) {
+            let b = [
+                chunk[0],
+                chunk.get(1).copied().unwrap_or(0),
+                chunk.get(2).copied().unwrap_or(0),
+            ];
+            let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+            // 3 bytes make 4 characters, a partial chunk makes 1 character
+            // more than its number of bytes.
+            let chars = chunk.len() + 1;
+            for i in 0..chars {
+                let idx = (n >> (18 - 6 * i)) & 0x3F;
+                dst.push(BASE64URL[idx as usize] as char);
+            }
+        }
+        Cow::Owned(dst)
+    }
+
+    fn decode<'a>(&self, value: &'a str) -> Result<Cow<'a, [u8]>, Error> {
+        let src = value.trim_end_matches('=').as_bytes();
+        if src.len() % 4 == 1 {
+            return Err(Error::invalid_value());
+        }
+
+        let mut dst = Vec::with_capacity(src.len() * 3 / 4);
+        for chunk in src.chunks(4) {
+            let mut n = 0u32;
+            for (i, &byte) in chunk.iter().enumerate() {
+                let bits = from_base64url(byte).ok_or_else(Error::invalid_value)?;
+                n |= (bits as u32) << (18 - 6 * i);
+            }
+            // 4 characters make 3 bytes, a partial chunk makes 1 byte less
+            // than its number of characters.
+            for i in 0..chunk.len() - 1 {
+                dst.push((n >> (16 - 8 * i)) as u8);
+            }
+        }
+        Ok(Cow::Owned(dst))
+    }
+}
+
+const BASE64URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+fn from_base64url(byte: u8) -> Option<u8> {
+    match byte {
+        b'A'..=b'Z' => Some(byte - b'A'),
+        b'a'..=b'z' => Some(byte - b'a' + 26),
+        b'0'..=b'9' => Some(byte - b'0' + 52),
+        b'-' => Some(62),
+        b'_' => Some(63),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity() {
+        assert_eq!(Identity.encode(b"foo"), "foo");
+        assert_eq!(Identity.decode("foo").unwrap(), &b"foo"[..]);
+    }
+
+    #[test]
+    fn percent_roundtrip() {
+        let raw = "a b,c;d\"é~".as_bytes();
+        let encoded = Percent.encode(raw);
+        assert_eq!(encoded, "a%20b%2Cc%3Bd%22%C3%A9~");
+        assert_eq!(Percent.decode(&encoded).unwrap(), raw);
+
+        assert!(matches!(Percent.encode(b"plain"), Cow::Borrowed("plain")));
+        assert!(matches!(Percent.decode("plain").unwrap(), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn percent_invalid() {
+        Percent.decode("%").expect_err("truncated escape");
+        Percent.decode("%4").expect_err("truncated escape");
+        Percent.decode("%zz").expect_err("non-hex escape");
+    }
+
+    #[test]
+    fn base64url_roundtrip() {
+        let cases: &[(&[u8], &str)] = &[
+            (b"", ""),
+            (b"f", "Zg"),
+            (b"fo", "Zm8"),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg"),
+            (b"\xfb\xff\xfe", "-__-"),
+        ];
+
+        for &(raw, encoded) in cases {
+            assert_eq!(Base64Url.encode(raw), encoded);
+            assert_eq!(Base64Url.decode(encoded).unwrap(), raw);
+        }
+
+        assert_eq!(Base64Url.decode("Zg==").unwrap(), &b"f"[..], "padding");
+    }
+
+    #[test]
+    fn base64url_invalid() {
+        Base64Url.decode("Z").expect_err("truncated");
+        Base64Url.decode("Zm9v+/").expect_err("standard alphabet");
+    }
+}
@@ -0,0 +1,97 @@
+use url::Url;
+
+/// The context of a request that cookies are selected for.
+///
+/// Decides which cookies a `CookieJar` sends with a request, beyond the
+/// URL: `HttpOnly` cookies are only sent by HTTP APIs, and `SameSite`
+/// cookies are only sent with some cross-site requests.
+///
+/// The default context is an unpartitioned HTTP request with a safe method
+/// that is same-site, such as the user typing the URL in.
+///
+/// # Example
+///
+/// ```
+/// use cookies::RequestContext;
+/// use url::Url;
+///
+/// // A form on another site posting to this one.
+/// let ctx = RequestContext::new()
+///     .site_for_cookies(Url::parse("https://rust-lang.org/").unwrap())
+///     .top_level_navigation(true)
+///     .method("POST");
+/// ```
+#[derive(Clone, Debug)]
+pub struct RequestContext {
+    pub(crate) site_for_cookies: Option<Url>,
+    pub(crate) top_level_site: Option<Url>,
+    pub(crate) top_level_navigation: bool,
+    pub(crate) safe_method: bool,
+    pub(crate) http_api: bool,
+}
+
+// ===== impl RequestContext =====
+
+impl RequestContext {
+    /// Create the default `RequestContext`.
+    pub fn new() -> RequestContext {
+        RequestContext {
+            site_for_cookies: None,
+            top_level_site: None,
+            top_level_navigation: false,
+            safe_method: true,
+            http_api: true,
+        }
+    }
+
+    /// Set the site the request is made from, such as the URL of the
+    /// top-level document.
+    ///
+    /// The request is cross-site if its URL has a different registrable
+    /// domain than this one.
+    pub fn site_for_cookies(mut self, site: Url) -> RequestContext {
+        self.site_for_cookies = Some(site);
+        self
+    }
+
+    /// Set the URL of the top-level document the request is made from, which
+    /// partitions the request.
+    ///
+    /// A partitioned request stores and sees `Partitioned` cookies in the
+    /// partition of the top-level site, such as for an embedded widget. A
+    /// request without one is the top-level request, partitioned by the
+    /// site of its own URL.
+    pub fn top_level_site(mut self, url: Url) -> RequestContext {
+        self.top_level_site = Some(url);
+        self
+    }
+
+    /// Set if the request is a top-level navigation, such as following a
+    /// link, as opposed to loading a subresource.
+    pub fn top_level_navigation(mut self, enabled: bool) -> RequestContext {
+        self.top_level_navigation = enabled;
+        self
+    }
+
+    /// Set the method of the request.
+    ///
+    /// `SameSite=Lax` cookies are only sent with cross-site top-level
+    /// navigations that use a safe method, such as `GET`.
+    pub fn method(mut self, method: impl AsRef<str>) -> RequestContext {
+        self.safe_method = matches!(method.as_ref(), "GET" | "HEAD" | "OPTIONS" | "TRACE");
+        self
+    }
+
+    /// Set if the cookies are for an HTTP API, as opposed to a non-HTTP API
+    /// such as `document.cookie`, which can't see `HttpOnly` cookies.
+    pub fn http_api(mut self, enabled: bool) -> RequestContext {
+        self.http_api = enabled;
+        self
+    }
+}
+
+impl Default for RequestContext {
+    fn default() -> RequestContext {
+        RequestContext::new()
+    }
+}
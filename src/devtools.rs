@@ -0,0 +1,313 @@
+//! Convert cookies to and from the JSON of browser automation tools.
+//!
+//! Playwright's `storageState.json` and Puppeteer's `page.cookies()` both
+//! use the cookie objects of the Chrome DevTools protocol, with `name`,
+//! `value`, `domain`, `path`, `expires` in seconds since the Unix epoch (or
+//! `-1` for a session cookie), `httpOnly`, `secure` and `sameSite`. A
+//! `domain` with a leading dot is a domain cookie, otherwise it is
+//! host-only.
+//!
+//! Requires the `serde` and `jar` features.
+//!
+//! # Example
+//!
+//! ```
+//! use cookies::{devtools, Cookie, CookieJar};
+//!
+//! let state = r#"{
+//!     "cookies": [{
+//!         "name": "session",
+//!         "value": "abc",
+//!         "domain": ".hyper.rs",
+//!         "path": "/",
+//!         "expires": -1,
+//!         "httpOnly": true,
+//!         "secure": true,
+//!         "sameSite": "Lax"
+//!     }],
+//!     "origins": []
+//! }"#;
+//!
+//! let mut jar = CookieJar::new();
+//! for cookie in devtools::from_storage_state(state).unwrap() {
+//!     jar.insert(cookie).unwrap();
+//! }
+//!
+//! let session = jar.get("hyper.rs", "/", "session").unwrap();
+//! assert!(session.http_only());
+//! assert!(!session.is_persistent());
+//!
+//! let json = devtools::to_storage_state(jar.iter()).unwrap();
+//! ```
+
+use std::borrow::Cow;
+
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+
+use crate::parse::{self, Domain};
+use crate::util::{from_unix, to_unix};
+use crate::{Error, Priority, SameSite, StoredCookie};
+
+/// The cookie object of the DevTools protocol.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Repr<'a> {
+    #[serde(borrow)]
+    name: Cow<'a, str>,
+    #[serde(borrow)]
+    value: Cow<'a, str>,
+    #[serde(borrow)]
+    domain: Cow<'a, str>,
+    #[serde(borrow, default = "root")]
+    path: Cow<'a, str>,
+    #[serde(default = "session")]
+    expires: f64,
+    #[serde(default)]
+    http_only: bool,
+    #[serde(default)]
+    secure: bool,
+    #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+    same_site: Option<Cow<'a, str>>,
+    #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+    priority: Option<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    partition_key: Option<PartitionKey<'a>>,
+}
+
+/// Older versions of Puppeteer use a string, newer ones an object.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+enum PartitionKey<'a> {
+    Site(#[serde(borrow)] Cow<'a, str>),
+    Object {
+        #[serde(borrow, rename = "sourceOrigin")]
+        source_origin: Cow<'a, str>,
+    },
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct StorageState<C> {
+    cookies: Vec<C>,
+    #[serde(default)]
+    origins: Vec<serde_json::Value>,
+}
+
+struct Imported(StoredCookie);
+
+struct Exported<'a>(&'a StoredCookie);
+
+fn root() -> Cow<'static, str> {
+    Cow::Borrowed("/")
+}
+
+fn session() -> f64 {
+    -1.0
+}
+
+/// Read the cookies of a Playwright `storageState.json`.
+///
+/// # Error
+///
+/// Returns an error if the JSON isn't a storage state, or if any cookie
+/// isn't valid.
+pub fn from_storage_state(json: &str) -> serde_json::Result<Vec<StoredCookie>> {
+    let state = serde_json::from_str::<StorageState<Imported>>(json)?;
+    Ok(state.cookies.into_iter().map(|c| c.0).collect())
+}
+
+/// Read an array of cookies, such as from Puppeteer's `page.cookies()`.
+///
+/// # Error
+///
+/// Returns an error if the JSON isn't an array of cookies, or if any
+/// cookie isn't valid.
+pub fn from_cookies(json: &str) -> serde_json::Result<Vec<StoredCookie>> {
+    let cookies = serde_json::from_str::<Vec<Imported>>(json)?;
+    Ok(cookies.into_iter().map(|c| c.0).collect())
+}
+
+/// Write cookies as a Playwright `storageState.json`, without any origins.
+///
+/// # Error
+///
+/// Returns an error if the cookies can't be serialized.
+pub fn to_storage_state<'a>(
+    cookies: impl IntoIterator<Item = &'a StoredCookie>,
+) -> serde_json::Result<String> {
+    serde_json::to_string(&StorageState {
+        cookies: cookies.into_iter().map(Exported).collect(),
+        origins: Vec::new(),
+    })
+}
+
+/// Write cookies as an array, such as for Puppeteer's `page.setCookie()`.
+///
+/// # Error
+///
+/// Returns an error if the cookies can't be serialized.
+pub fn to_cookies<'a>(
+    cookies: impl IntoIterator<Item = &'a StoredCookie>,
+) -> serde_json::Result<String> {
+    serde_json::to_string(&cookies.into_iter().map(Exported).collect::<Vec<_>>())
+}
+
+// ===== impl Imported =====
+
+impl<'de> Deserialize<'de> for Imported {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = Repr::deserialize(deserializer)?;
+
+        parse::validate_name(&repr.name).map_err(de::Error::custom)?;
+        parse::validate_value(&repr.value).map_err(de::Error::custom)?;
+        if !parse::is_valid_path(&repr.path) {
+            return Err(de::Error::custom(Error::invalid_path()));
+        }
+        let (domain, host_only) = match parse::validate_domain(&repr.domain) {
+            Domain::AsIs => (&repr.domain[..], true),
+            Domain::LeadingDot => (&repr.domain[1..], false),
+            Domain::Invalid => return Err(de::Error::custom(Error::invalid_domain())),
+        };
+
+        let same_site = repr
+            .same_site
+            .as_deref()
+            .map(|ss| {
+                ss.parse::<SameSite>()
+                    .map_err(|_| de::Error::unknown_variant(ss, SameSite::VARIANTS))
+            })
+            .transpose()?;
+
+        let priority = repr
+            .priority
+            .as_deref()
+            .map(|p| {
+                p.parse::<Priority>()
+                    .map_err(|_| de::Error::unknown_variant(p, Priority::VARIANTS))
+            })
+            .transpose()?;
+
+        let mut cookie = StoredCookie::new(
+            repr.name.into_owned(),
+            repr.value.into_owned(),
+            domain.to_ascii_lowercase(),
+            repr.path.into_owned(),
+        );
+        // Any negative expiry is a session cookie.
+        if repr.expires >= 0.0 {
+            cookie.expiry = Some(from_unix(repr.expires as i64));
+        }
+        cookie.host_only = host_only;
+        cookie.secure = repr.secure;
+        cookie.http_only = repr.http_only;
+        cookie.same_site = same_site;
+        cookie.priority = priority;
+        cookie.partition_key = repr.partition_key.map(|key| match key {
+            PartitionKey::Site(site) => site.into_owned(),
+            PartitionKey::Object { source_origin } => source_origin.into_owned(),
+        });
+        Ok(Imported(cookie))
+    }
+}
+
+// ===== impl Exported =====
+
+impl<'a> Serialize for Exported<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let c = self.0;
+
+        let domain = if c.host_only {
+            Cow::Borrowed(&c.domain[..])
+        } else {
+            Cow::Owned(format!(".{}", c.domain))
+        };
+
+        Repr {
+            name: Cow::Borrowed(&c.name),
+            value: Cow::Borrowed(&c.value),
+            domain,
+            path: Cow::Borrowed(&c.path),
+            expires: c.expiry.map_or(-1.0, |expiry| to_unix(expiry) as f64),
+            http_only: c.http_only,
+            secure: c.secure,
+            same_site: c.same_site.map(|ss| Cow::Borrowed(ss.as_str())),
+            priority: c.priority.map(|p| Cow::Borrowed(p.as_str())),
+            partition_key: c
+                .partition_key
+                .as_deref()
+                .map(|key| PartitionKey::Site(Cow::Borrowed(key))),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cookie;
+
+    #[test]
+    fn puppeteer_cookies() {
+        let json = r#"[
+            {
+                "name": "a",
+                "value": "1",
+                "domain": "www.hyper.rs",
+                "path": "/guides",
+                "expires": 1558473131.25,
+                "size": 2,
+                "httpOnly": false,
+                "secure": true,
+                "session": false,
+                "sameSite": "Strict",
+                "priority": "High",
+                "sourceScheme": "Secure"
+            },
+            {
+                "name": "b",
+                "value": "2",
+                "domain": "widget.example",
+                "path": "/",
+                "expires": -1,
+                "httpOnly": true,
+                "secure": true,
+                "partitionKey": {"sourceOrigin": "https://hyper.rs", "hasCrossSiteAncestor": false}
+            }
+        ]"#;
+
+        let cookies = from_cookies(json).unwrap();
+        assert_eq!(cookies.len(), 2);
+
+        let a = &cookies[0];
+        assert!(a.host_only());
+        assert_eq!(a.path(), Some("/guides"));
+        assert_eq!(a.expires(), Some(from_unix(1_558_473_131)));
+        assert_eq!(a.same_site(), Some(SameSite::STRICT));
+        assert_eq!(a.priority(), Some(Priority::HIGH));
+
+        let b = &cookies[1];
+        assert!(!b.is_persistent());
+        assert!(b.http_only());
+        assert_eq!(b.partition_key(), Some("https://hyper.rs"));
+    }
+
+    #[test]
+    fn storage_state_roundtrip() {
+        let json = r#"{"cookies":[{"name":"a","value":"1","domain":".hyper.rs","path":"/","expires":1558473131.0,"httpOnly":true,"secure":false,"sameSite":"None"}],"origins":[]}"#;
+
+        let cookies = from_storage_state(json).unwrap();
+        assert!(!cookies[0].host_only());
+        assert_eq!(cookies[0].host(), "hyper.rs");
+
+        assert_eq!(to_storage_state(&cookies).unwrap(), json);
+    }
+
+    #[test]
+    fn validates() {
+        from_cookies(r#"[{"name":"a b","value":"1","domain":"hyper.rs"}]"#)
+            .expect_err("invalid name");
+        from_cookies(r#"[{"name":"a","value":"1","domain":"hyper.rs","sameSite":"Sometimes"}]"#)
+            .expect_err("unknown sameSite");
+        from_storage_state(r#"[]"#).expect_err("not a storage state");
+    }
+}
@@ -13,6 +13,11 @@ enum Kind {
     InvalidPath,
     InvalidDomain,
     TooLong,
+    InvalidAttribute,
+    #[cfg(feature = "jar")]
+    NotSecure,
+    #[cfg(feature = "jar")]
+    HttpOnly,
 }
 
 // ===== impl Error =====
@@ -47,6 +52,26 @@ impl Error {
             kind: Kind::TooLong,
         }
     }
+
+    pub(crate) fn invalid_attribute() -> Error {
+        Error {
+            kind: Kind::InvalidAttribute,
+        }
+    }
+
+    #[cfg(feature = "jar")]
+    pub(crate) fn not_secure() -> Error {
+        Error {
+            kind: Kind::NotSecure,
+        }
+    }
+
+    #[cfg(feature = "jar")]
+    pub(crate) fn http_only() -> Error {
+        Error {
+            kind: Kind::HttpOnly,
+        }
+    }
 }
 
 impl fmt::Display for Error {
@@ -57,6 +82,11 @@ impl fmt::Display for Error {
             Kind::InvalidPath => f.write_str("cookie path is invalid"),
             Kind::InvalidDomain => f.write_str("cookie domain is invalid"),
             Kind::TooLong => f.write_str("cookie string is too long"),
+            Kind::InvalidAttribute => f.write_str("cookie attribute value is unknown"),
+            #[cfg(feature = "jar")]
+            Kind::NotSecure => f.write_str("cookie requires the Secure attribute"),
+            #[cfg(feature = "jar")]
+            Kind::HttpOnly => f.write_str("HttpOnly cookie can't be set from a non-HTTP API"),
         }
     }
 }
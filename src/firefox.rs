@@ -0,0 +1,249 @@
+//! Import cookies from a Firefox profile.
+//!
+//! Firefox stores its cookies in the `moz_cookies` table of the
+//! `cookies.sqlite` file in the profile directory.
+//!
+//! Requires the `firefox` feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use cookies::{firefox, CookieJar};
+//!
+//! let mut jar = CookieJar::new();
+//! for cookie in firefox::read("/path/to/profile/cookies.sqlite").unwrap() {
+//!     match cookie {
+//!         Ok(cookie) => {
+//!             // Cookies no site could set, such as for `com`, are rejected.
+//!             let _ = jar.insert(cookie);
+//!         }
+//!         Err(err) => eprintln!("skipped: {}", err),
+//!     }
+//! }
+//! ```
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::codec::{Codec, Percent};
+use crate::parse::{self, Domain};
+use crate::sqlite::{self, OpenError};
+use crate::{SameSite, StoredCookie};
+
+/// An error reading a `cookies.sqlite` file, or one of its cookies.
+#[derive(Debug)]
+pub struct Error {
+    kind: Kind,
+    // The host and name of the cookie, for an error with a single cookie.
+    cookie: Option<(String, String)>,
+}
+
+#[derive(Debug)]
+enum Kind {
+    Io(io::Error),
+    Sqlite(rusqlite::Error),
+    Invalid(crate::Error),
+}
+
+// Firefox used to store the expiry in seconds, and now in milliseconds. No
+// expiry in seconds is this large, it would be in the year 5138.
+const EXPIRY_MILLIS: i64 = 100_000_000_000;
+
+/// Read all the cookies of a Firefox `cookies.sqlite` file.
+///
+/// The file is opened read-only, and can be read while Firefox is running,
+/// though cookies it hasn't written yet will be missing.
+///
+/// Each cookie is either imported, or an error if it isn't a valid cookie.
+/// A cookie in a partition, from its `originAttributes`, is imported as
+/// partitioned.
+///
+/// # Error
+///
+/// Returns an error if the file can't be opened, or doesn't have a
+/// `moz_cookies` table.
+pub fn read(path: impl AsRef<Path>) -> Result<Vec<Result<StoredCookie, Error>>, Error> {
+    let conn = sqlite::open(path.as_ref())?;
+
+    let mut stmt = conn.prepare(
+        "SELECT name, value, host, path, expiry, creationTime, lastAccessed, \
+                isSecure, isHttpOnly, sameSite, rawSameSite, originAttributes \
+         FROM moz_cookies",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(Row {
+            name: row.get(0)?,
+            value: row.get(1)?,
+            host: row.get(2)?,
+            path: row.get(3)?,
+            expiry: row.get(4)?,
+            creation: row.get(5)?,
+            last_access: row.get(6)?,
+            secure: row.get(7)?,
+            http_only: row.get(8)?,
+            same_site: row.get(9)?,
+            raw_same_site: row.get(10)?,
+            origin_attributes: row.get(11)?,
+        })
+    })?;
+
+    let mut cookies = Vec::new();
+    for row in rows {
+        let row = row?;
+        let cookie = row.clone_id();
+        cookies.push(row.into_cookie().map_err(|kind| Error {
+            kind,
+            cookie: Some(cookie),
+        }));
+    }
+    Ok(cookies)
+}
+
+struct Row {
+    name: String,
+    value: String,
+    host: String,
+    path: String,
+    expiry: i64,
+    creation: i64,
+    last_access: i64,
+    secure: bool,
+    http_only: bool,
+    same_site: i64,
+    raw_same_site: i64,
+    origin_attributes: String,
+}
+
+impl Row {
+    fn clone_id(&self) -> (String, String) {
+        (self.host.clone(), self.name.clone())
+    }
+
+    fn into_cookie(self) -> Result<StoredCookie, Kind> {
+        parse::validate_name(&self.name).map_err(Kind::Invalid)?;
+        parse::validate_value(&self.value).map_err(Kind::Invalid)?;
+        if !parse::is_valid_path(&self.path) {
+            return Err(Kind::Invalid(crate::Error::invalid_path()));
+        }
+        let (domain, host_only) = match parse::validate_domain(&self.host) {
+            Domain::AsIs => (&self.host[..], true),
+            Domain::LeadingDot => (&self.host[1..], false),
+            Domain::Invalid => return Err(Kind::Invalid(crate::Error::invalid_domain())),
+        };
+
+        let mut cookie = StoredCookie::new(
+            self.name,
+            self.value,
+            domain.to_ascii_lowercase(),
+            self.path,
+        );
+        cookie.expiry = Some(if self.expiry >= EXPIRY_MILLIS {
+            from_micros(self.expiry.saturating_mul(1_000))
+        } else {
+            from_micros(self.expiry.saturating_mul(1_000_000))
+        });
+        cookie.creation = from_micros(self.creation);
+        cookie.last_access.set(from_micros(self.last_access));
+        cookie.host_only = host_only;
+        cookie.secure = self.secure;
+        cookie.http_only = self.http_only;
+        // `sameSite` is what Firefox enforces, which is Lax by default when
+        // the attribute was unspecified. `rawSameSite` is 0 exactly then.
+        cookie.same_site = match self.same_site {
+            _ if self.raw_same_site == 0 => None,
+            0 => Some(SameSite::NONE),
+            1 => Some(SameSite::LAX),
+            2 => Some(SameSite::STRICT),
+            _ => None,
+        };
+        cookie.partition_key = partition_key(&self.origin_attributes);
+        Ok(cookie)
+    }
+}
+
+fn from_micros(micros: i64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_micros(micros.max(0) as u64)
+}
+
+/// Get the partition key from origin attributes such as
+/// `^partitionKey=%28https%2Chyper.rs%29`, as `https://hyper.rs`.
+fn partition_key(origin_attributes: &str) -> Option<String> {
+    let attrs = origin_attributes.strip_prefix('^')?;
+    let encoded = attrs
+        .split('&')
+        .find_map(|attr| attr.strip_prefix("partitionKey="))?;
+    let decoded = Percent.decode(encoded).ok()?;
+    let key = std::str::from_utf8(&decoded).ok()?;
+
+    // `(scheme,site)`, or `(scheme,site,port)`.
+    let mut parts = key.strip_prefix('(')?.strip_suffix(')')?.split(',');
+    let scheme = parts.next()?;
+    let site = parts.next()?;
+    Some(format!("{}://{}", scheme, site))
+}
+
+// ===== impl Error =====
+
+impl From<OpenError> for Error {
+    fn from(err: OpenError) -> Error {
+        let kind = match err {
+            OpenError::Io(err) => Kind::Io(err),
+            OpenError::Sqlite(err) => Kind::Sqlite(err),
+        };
+        Error { kind, cookie: None }
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(err: rusqlite::Error) -> Error {
+        Error {
+            kind: Kind::Sqlite(err),
+            cookie: None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("reading Firefox cookies: ")?;
+        if let Some((ref host, ref name)) = self.cookie {
+            write!(f, "cookie {:?} for {:?}: ", name, host)?;
+        }
+        match self.kind {
+            Kind::Io(ref err) => fmt::Display::fmt(err, f),
+            Kind::Sqlite(ref err) => fmt::Display::fmt(err, f),
+            Kind::Invalid(ref err) => fmt::Display::fmt(err, f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self.kind {
+            Kind::Io(ref err) => Some(err),
+            Kind::Sqlite(ref err) => Some(err),
+            Kind::Invalid(ref err) => Some(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partition_keys() {
+        assert_eq!(
+            partition_key("^partitionKey=%28https%2Chyper.rs%29").as_deref(),
+            Some("https://hyper.rs")
+        );
+        assert_eq!(
+            partition_key("^userContextId=1&partitionKey=%28http%2Chyper.rs%2C8080%29").as_deref(),
+            Some("http://hyper.rs")
+        );
+        assert_eq!(partition_key("^userContextId=1"), None);
+        assert_eq!(partition_key(""), None);
+    }
+}
@@ -0,0 +1,474 @@
+//! Extract cookies from an HTTP Archive (HAR 1.2) file.
+//!
+//! A HAR file, as saved by browser developer tools or a proxy, lists the
+//! requests of a capture with their responses. Each request and response
+//! has its cookies as objects, and its raw `Cookie` and `Set-Cookie`
+//! headers. The raw headers are preferred, since the objects leave out
+//! attributes such as `SameSite`.
+//!
+//! Requires the `serde` and `jar` features.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use cookies::{har, CookieJar};
+//!
+//! let json = std::fs::read_to_string("capture.har").unwrap();
+//!
+//! for entry in har::parse(&json).unwrap() {
+//!     for cookie in entry.response_cookies() {
+//!         println!("{} set {}", entry.url(), cookie);
+//!     }
+//! }
+//!
+//! let mut jar = CookieJar::new();
+//! har::replay(&json, &mut jar).unwrap();
+//! ```
+
+use std::fmt;
+
+use url::Url;
+
+use crate::util::from_unix;
+use crate::{Builder, CookieJar, OwnedCookie, RequestContext, SameSite, StoredCookie};
+
+/// A request of a HAR file, with the cookies it sent and received.
+#[derive(Clone, Debug)]
+pub struct Entry {
+    method: String,
+    url: Url,
+    request_cookies: Vec<(String, String)>,
+    response_cookies: Vec<OwnedCookie>,
+}
+
+/// An error reading a HAR file.
+#[derive(Debug)]
+pub struct Error {
+    kind: Kind,
+}
+
+#[derive(Debug)]
+enum Kind {
+    Json(serde_json::Error),
+    Url { entry: usize, err: url::ParseError },
+}
+
+#[derive(serde::Deserialize)]
+struct Har {
+    log: Log,
+}
+
+#[derive(serde::Deserialize)]
+struct Log {
+    entries: Vec<RawEntry>,
+}
+
+#[derive(serde::Deserialize)]
+struct RawEntry {
+    request: Request,
+    response: Response,
+}
+
+#[derive(serde::Deserialize)]
+struct Request {
+    method: String,
+    url: String,
+    #[serde(default)]
+    headers: Vec<Header>,
+    #[serde(default)]
+    cookies: Vec<RawCookie>,
+}
+
+#[derive(serde::Deserialize)]
+struct Response {
+    #[serde(default)]
+    headers: Vec<Header>,
+    #[serde(default)]
+    cookies: Vec<RawCookie>,
+}
+
+#[derive(serde::Deserialize)]
+struct Header {
+    name: String,
+    value: String,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawCookie {
+    name: String,
+    value: String,
+    path: Option<String>,
+    domain: Option<String>,
+    expires: Option<String>,
+    http_only: Option<bool>,
+    secure: Option<bool>,
+    same_site: Option<String>,
+}
+
+/// Parse the entries of a HAR file, in the order they're listed.
+///
+/// Cookies that aren't valid are skipped, as a browser would ignore them.
+///
+/// # Error
+///
+/// Returns an error if the JSON isn't a HAR file, or if the URL of an entry
+/// isn't valid.
+pub fn parse(json: &str) -> Result<Vec<Entry>, Error> {
+    let har = serde_json::from_str::<Har>(json).map_err(|err| Error {
+        kind: Kind::Json(err),
+    })?;
+
+    har.log
+        .entries
+        .into_iter()
+        .enumerate()
+        .map(|(i, raw)| Entry::from_raw(i, raw))
+        .collect()
+}
+
+/// Fill a jar by replaying the entries of a HAR file in order.
+///
+/// The cookies each response sets are stored as if received from the URL
+/// of its request, and are rejected as in `CookieJar::store`. A cookie sent
+/// by a request that the jar wouldn't have sent, such as one set before the
+/// capture started, is stored as a host-only session cookie for the root
+/// path of the request's host.
+///
+/// # Error
+///
+/// Returns an error as in `parse`, in which case the jar is unchanged.
+pub fn replay(json: &str, jar: &mut CookieJar) -> Result<(), Error> {
+    for entry in parse(json)? {
+        entry.replay(jar);
+    }
+    Ok(())
+}
+
+fn header_values<'a>(headers: &'a [Header], name: &'a str) -> impl Iterator<Item = &'a str> {
+    headers
+        .iter()
+        .filter(move |h| h.name.eq_ignore_ascii_case(name))
+        .map(|h| &h.value[..])
+}
+
+/// Convert a cookie object into an `OwnedCookie`, or `None` if it isn't
+/// valid.
+fn from_object(raw: &RawCookie) -> Option<OwnedCookie> {
+    let same_site = raw
+        .same_site
+        .as_deref()
+        .and_then(|ss| ss.parse::<SameSite>().ok());
+
+    let domain = raw
+        .domain
+        .as_deref()
+        .map(|domain| domain.strip_prefix('.').unwrap_or(domain));
+    let expires = raw.expires.as_deref().and_then(parse_date);
+
+    let cookie = Builder::new(&raw.name, &raw.value)
+        .maybe_path(raw.path.as_deref())
+        .maybe_domain(domain)
+        .maybe_expires(expires)
+        .secure(raw.secure.unwrap_or(false))
+        .http_only(raw.http_only.unwrap_or(false))
+        .same_site(same_site)
+        .build()
+        .ok()?;
+    Some(OwnedCookie::from(&cookie))
+}
+
+/// Parse an ISO 8601 date, such as `2019-05-21T21:12:11.000Z`.
+fn parse_date(s: &str) -> Option<std::time::SystemTime> {
+    let (datetime, rest) = (s.get(..19)?, &s[19..]);
+    let secs = time::strptime(datetime, "%Y-%m-%dT%H:%M:%S")
+        .ok()?
+        .to_timespec()
+        .sec;
+
+    let rest = match rest.strip_prefix('.') {
+        Some(fraction) => fraction.trim_start_matches(|c: char| c.is_ascii_digit()),
+        None => rest,
+    };
+    let offset = match rest.as_bytes() {
+        b"" | b"Z" | b"z" => 0,
+        [sign @ (b'+' | b'-'), h1, h2, b':', m1, m2] => {
+            let hours = two_digits(*h1, *h2)?;
+            let minutes = two_digits(*m1, *m2)?;
+            if hours > 24 || minutes > 59 || (hours == 24 && minutes > 0) {
+                return None;
+            }
+            let offset = i64::from(hours) * 3600 + i64::from(minutes) * 60;
+            if *sign == b'+' {
+                offset
+            } else {
+                -offset
+            }
+        }
+        _ => return None,
+    };
+    Some(from_unix(secs.checked_sub(offset)?))
+}
+
+fn two_digits(tens: u8, ones: u8) -> Option<u8> {
+    if tens.is_ascii_digit() && ones.is_ascii_digit() {
+        Some((tens - b'0') * 10 + (ones - b'0'))
+    } else {
+        None
+    }
+}
+
+// ===== impl Entry =====
+
+impl Entry {
+    fn from_raw(index: usize, raw: RawEntry) -> Result<Entry, Error> {
+        let url = Url::parse(&raw.request.url).map_err(|err| Error {
+            kind: Kind::Url { entry: index, err },
+        })?;
+
+        let mut request_cookies = header_values(&raw.request.headers, "cookie")
+            .flat_map(crate::parse_pairs)
+            .map(|(name, value)| (name.to_owned(), value.to_owned()))
+            .collect::<Vec<_>>();
+        if request_cookies.is_empty() {
+            request_cookies = raw
+                .request
+                .cookies
+                .iter()
+                .map(|c| (c.name.clone(), c.value.clone()))
+                .collect();
+        }
+
+        // Some tools join several `Set-Cookie` headers with newlines.
+        let mut response_cookies = header_values(&raw.response.headers, "set-cookie")
+            .flat_map(str::lines)
+            .filter_map(|line| crate::parse(line).ok().map(|c| OwnedCookie::from(&c)))
+            .collect::<Vec<_>>();
+        if response_cookies.is_empty() {
+            response_cookies = raw
+                .response
+                .cookies
+                .iter()
+                .filter_map(from_object)
+                .collect();
+        }
+
+        Ok(Entry {
+            method: raw.request.method,
+            url,
+            request_cookies,
+            response_cookies,
+        })
+    }
+
+    /// Get the method of the request.
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// Get the URL of the request.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Get the name/value pairs of the cookies sent with the request.
+    pub fn request_cookies(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.request_cookies
+            .iter()
+            .map(|(name, value)| (&name[..], &value[..]))
+    }
+
+    /// Get the cookies set by the response.
+    pub fn response_cookies(&self) -> &[OwnedCookie] {
+        &self.response_cookies
+    }
+
+    fn replay(&self, jar: &mut CookieJar) {
+        let ctx = RequestContext::new().method(&self.method);
+
+        let sent = jar
+            .cookies_for(&self.url, &ctx)
+            .into_iter()
+            .map(|c| c.name.clone())
+            .collect::<Vec<_>>();
+        if let Some(host) = self.url.host_str() {
+            for (name, value) in &self.request_cookies {
+                if sent.contains(name) {
+                    continue;
+                }
+                let mut cookie =
+                    StoredCookie::new(name.clone(), value.clone(), host.to_owned(), "/".into());
+                cookie.host_only = true;
+                let _ = jar.insert(cookie);
+            }
+        }
+
+        for cookie in &self.response_cookies {
+            let _ = jar.store_with_context(&self.url, cookie, &ctx);
+        }
+    }
+}
+
+// ===== impl Error =====
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("reading HAR: ")?;
+        match self.kind {
+            Kind::Json(ref err) => fmt::Display::fmt(err, f),
+            Kind::Url { entry, ref err } => write!(f, "entry {}: invalid URL: {}", entry, err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self.kind {
+            Kind::Json(ref err) => Some(err),
+            Kind::Url { ref err, .. } => Some(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Cookie;
+
+    const HAR: &str = r#"{
+        "log": {
+            "version": "1.2",
+            "creator": {"name": "test", "version": "1"},
+            "entries": [
+                {
+                    "startedDateTime": "2019-05-21T21:12:10.000Z",
+                    "request": {
+                        "method": "POST",
+                        "url": "https://www.hyper.rs/login",
+                        "httpVersion": "HTTP/1.1",
+                        "headers": [{"name": "cookie", "value": "old=1; lang=en"}],
+                        "cookies": [{"name": "old", "value": "1"}, {"name": "lang", "value": "en"}]
+                    },
+                    "response": {
+                        "status": 302,
+                        "headers": [
+                            {"name": "Set-Cookie", "value": "session=abc; Domain=hyper.rs; Path=/; Secure; HttpOnly; SameSite=Lax\nlang=de"},
+                            {"name": "Set-Cookie", "value": "bad cookie"}
+                        ],
+                        "cookies": []
+                    }
+                },
+                {
+                    "startedDateTime": "2019-05-21T21:12:11.000Z",
+                    "request": {
+                        "method": "GET",
+                        "url": "https://www.hyper.rs/guides",
+                        "headers": [],
+                        "cookies": []
+                    },
+                    "response": {
+                        "status": 200,
+                        "headers": [],
+                        "cookies": [{
+                            "name": "seen",
+                            "value": "1",
+                            "path": "/guides",
+                            "domain": ".hyper.rs",
+                            "expires": "2100-01-01T01:00:00.000+01:00",
+                            "httpOnly": false,
+                            "secure": true
+                        }]
+                    }
+                }
+            ]
+        }
+    }"#;
+
+    #[test]
+    fn entries() {
+        let entries = parse(HAR).unwrap();
+        assert_eq!(entries.len(), 2);
+
+        let login = &entries[0];
+        assert_eq!(login.method(), "POST");
+        assert_eq!(
+            login.request_cookies().collect::<Vec<_>>(),
+            [("old", "1"), ("lang", "en")]
+        );
+        let set = login.response_cookies();
+        assert_eq!(set.len(), 2, "invalid Set-Cookie skipped");
+        assert_eq!(set[0].name(), "session");
+        assert_eq!(set[0].same_site(), Some(SameSite::LAX));
+        assert_eq!(set[1].value(), "de");
+
+        let guides = &entries[1];
+        assert_eq!(guides.request_cookies().count(), 0);
+        let seen = &guides.response_cookies()[0];
+        assert_eq!(seen.domain(), Some("hyper.rs"));
+        assert_eq!(seen.path(), Some("/guides"));
+        assert!(seen.secure());
+        assert_eq!(seen.expires(), Some(from_unix(4_102_444_800)));
+    }
+
+    #[test]
+    fn replay_into_jar() {
+        let mut jar = CookieJar::new();
+        replay(HAR, &mut jar).unwrap();
+
+        let old = jar.get("www.hyper.rs", "/", "old").unwrap();
+        assert!(old.host_only());
+        assert!(!old.is_persistent());
+
+        // Set by the response after being sent.
+        assert_eq!(jar.get("www.hyper.rs", "/", "lang").unwrap().value(), "de");
+        assert!(jar.get("hyper.rs", "/", "session").unwrap().http_only());
+        assert!(jar.get("hyper.rs", "/guides", "seen").is_some());
+
+        let url = Url::parse("https://www.hyper.rs/guides").unwrap();
+        assert_eq!(
+            jar.cookie_header(&url, &RequestContext::new()).as_deref(),
+            Some("seen=1; old=1; lang=de; session=abc")
+        );
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(
+            parse_date("2019-05-21T21:12:11Z"),
+            Some(from_unix(1_558_473_131))
+        );
+        assert_eq!(
+            parse_date("2019-05-21T23:12:11.123+02:00"),
+            Some(from_unix(1_558_473_131))
+        );
+        assert_eq!(
+            parse_date("2019-05-21T20:42:11-00:30"),
+            Some(from_unix(1_558_473_131))
+        );
+        assert_eq!(parse_date("2019-05-21"), None);
+        assert_eq!(parse_date("2019-05-21T21:12:11 UTC"), None);
+        assert_eq!(parse_date("2019-05-21T21:12:11+24:01"), None);
+        assert_eq!(parse_date("2019-05-21T21:12:11+2:00"), None);
+        assert_eq!(parse_date("2019-05-21T21:12:11+-1:00"), None);
+        assert_eq!(
+            parse_date("2019-05-21T21:12:11+99999999999999999999:00"),
+            None
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let err = parse("{}").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("reading HAR: missing field `log`"));
+
+        let json =
+            r#"{"log":{"entries":[{"request":{"method":"GET","url":"/relative"},"response":{}}]}}"#;
+        let err = parse(json).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "reading HAR: entry 0: invalid URL: relative URL without a base"
+        );
+    }
+}
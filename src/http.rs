@@ -0,0 +1,172 @@
+//! Integration with the `http` crate.
+//!
+//! Requires the `http` feature.
+//!
+//! # Example
+//!
+//! ```
+//! use cookies::http::HeaderMapExt;
+//! use cookies::{Builder, Cookie};
+//! use http::HeaderMap;
+//!
+//! let c = Builder::new("foo", "bar").path("/").build().unwrap();
+//!
+//! let mut headers = HeaderMap::new();
+//! headers.append_set_cookie(&c).unwrap();
+//!
+//! for cookie in headers.set_cookies() {
+//!     let cookie = cookie.expect("valid Set-Cookie");
+//!     assert_eq!(cookie.name(), "foo");
+//!     assert_eq!(cookie.path(), Some("/"));
+//! }
+//! ```
+
+use std::convert::TryFrom;
+
+use bytes::{BufMut, BytesMut};
+use http::header::{HeaderMap, HeaderValue, InvalidHeaderValue, COOKIE, SET_COOKIE};
+
+use crate::{Cookie, Error};
+
+/// Convert a `Cookie` into a `Set-Cookie` header value.
+///
+/// The cookie is written directly into the bytes of the header value.
+///
+/// # Error
+///
+/// Returns an error if the cookie contains bytes not allowed in a header
+/// value. A cookie checked by `Builder` or `parse` never does, but one
+/// imported from a file may.
+pub fn to_header_value(cookie: &dyn Cookie) -> Result<HeaderValue, InvalidHeaderValue> {
+    let mut dst = BytesMut::with_capacity(crate::serialized_len(cookie)).writer();
+    crate::write_set_cookie(cookie, &mut dst).expect("BytesMut never fails to write");
+    HeaderValue::from_maybe_shared(dst.into_inner().freeze())
+}
+
+/// Parse a `Set-Cookie` header value as a `Cookie`.
+///
+/// # Error
+///
+/// Returns an error if the header value isn't valid UTF-8, or doesn't
+/// parse as a cookie. See `cookies::parse`.
+pub fn parse(value: &HeaderValue) -> Result<impl Cookie + '_, Error> {
+    let s = std::str::from_utf8(value.as_bytes()).map_err(|_| Error::invalid_value())?;
+    crate::parse(s)
+}
+
+/// Extension methods for cookies in an `http::HeaderMap`.
+///
+/// This trait is sealed, and only implemented for `HeaderMap`.
+pub trait HeaderMapExt: sealed::Sealed {
+    /// Append a `Set-Cookie` header for a `Cookie`.
+    ///
+    /// # Error
+    ///
+    /// Returns an error as in `to_header_value`, in which case no header
+    /// is appended.
+    fn append_set_cookie(&mut self, cookie: &dyn Cookie) -> Result<(), InvalidHeaderValue>;
+
+    /// Iterate over all the `Set-Cookie` headers, parsed as cookies.
+    fn set_cookies(&self) -> impl Iterator<Item = Result<impl Cookie + '_, Error>> + '_;
+
+    /// Iterate over the name/value pairs of all the `Cookie` headers.
+    ///
+    /// Header values that aren't valid UTF-8 are skipped.
+    fn cookie_pairs(&self) -> impl Iterator<Item = (&str, &str)> + '_;
+}
+
+impl<'a> TryFrom<&'a dyn Cookie> for HeaderValue {
+    type Error = InvalidHeaderValue;
+
+    fn try_from(cookie: &'a dyn Cookie) -> Result<HeaderValue, InvalidHeaderValue> {
+        to_header_value(cookie)
+    }
+}
+
+impl HeaderMapExt for HeaderMap {
+    fn append_set_cookie(&mut self, cookie: &dyn Cookie) -> Result<(), InvalidHeaderValue> {
+        self.append(SET_COOKIE, to_header_value(cookie)?);
+        Ok(())
+    }
+
+    fn set_cookies(&self) -> impl Iterator<Item = Result<impl Cookie + '_, Error>> + '_ {
+        self.get_all(SET_COOKIE).iter().map(parse)
+    }
+
+    fn cookie_pairs(&self) -> impl Iterator<Item = (&str, &str)> + '_ {
+        self.get_all(COOKIE)
+            .iter()
+            .filter_map(|value| std::str::from_utf8(value.as_bytes()).ok())
+            .flat_map(crate::parse_pairs)
+    }
+}
+
+impl sealed::Sealed for HeaderMap {}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Builder;
+
+    #[test]
+    fn header_value() {
+        let c = Builder::new("foo", "bar")
+            .domain("hyper.rs")
+            .http_only(true)
+            .build()
+            .unwrap();
+
+        let value = to_header_value(&c).unwrap();
+        assert_eq!(value, "foo=bar; Domain=hyper.rs; HttpOnly");
+
+        let parsed = parse(&value).unwrap();
+        assert_eq!(parsed.domain(), Some("hyper.rs"));
+        assert!(parsed.http_only());
+    }
+
+    #[cfg(feature = "jar")]
+    #[test]
+    fn header_value_invalid() {
+        // Imported cookies aren't checked like built or parsed ones.
+        let c =
+            crate::StoredCookie::new("foo".into(), "a\nb".into(), "hyper.rs".into(), "/".into());
+
+        to_header_value(&c).expect_err("newline in value");
+        HeaderValue::try_from(&c as &dyn Cookie).expect_err("newline in value");
+
+        let mut headers = HeaderMap::new();
+        headers.append_set_cookie(&c).expect_err("newline in value");
+        assert!(headers.is_empty());
+    }
+
+    #[test]
+    fn header_map_set_cookies() {
+        let a = Builder::new("a", "1").build().unwrap();
+        let b = Builder::new("b", "2").build().unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.append_set_cookie(&a).unwrap();
+        headers.append_set_cookie(&b).unwrap();
+        headers.append(SET_COOKIE, HeaderValue::from_static("=bad"));
+
+        let parsed = headers.set_cookies().collect::<Vec<_>>();
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed[0].as_ref().unwrap().name(), "a");
+        assert_eq!(parsed[1].as_ref().unwrap().name(), "b");
+        assert!(parsed[2].is_err());
+    }
+
+    #[test]
+    fn header_map_cookie_pairs() {
+        let mut headers = HeaderMap::new();
+        headers.append(COOKIE, HeaderValue::from_static("a=1; b=2"));
+        headers.append(COOKIE, HeaderValue::from_static("c=3"));
+
+        let pairs = headers.cookie_pairs().collect::<Vec<_>>();
+        assert_eq!(pairs, vec![("a", "1"), ("b", "2"), ("c", "3")]);
+    }
+}
@@ -0,0 +1,1533 @@
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::fmt;
+use std::net::Ipv4Addr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use url::{Host, Url};
+
+use super::{Cookie, Error, Priority, SameSite, Sealed};
+use crate::{util, PublicSuffixList, RequestContext};
+
+/// A store of cookies, following the storage model of RFC 6265.
+///
+/// Cookies received in a `Set-Cookie` header are stored for the URL of the
+/// request, which decides their domain and default path.
+///
+/// Requires the `jar` feature.
+///
+/// # Example
+///
+/// ```
+/// use cookies::CookieJar;
+/// use url::Url;
+///
+/// let url = Url::parse("https://www.hyper.rs/guides/client").unwrap();
+///
+/// let mut jar = CookieJar::new();
+/// jar.store_set_cookie(&url, "session=abc; Domain=hyper.rs").unwrap();
+/// jar.store_set_cookie(&url, "lang=en").unwrap();
+///
+/// let session = jar.get("hyper.rs", "/guides", "session").unwrap();
+/// assert!(!session.host_only());
+///
+/// let lang = jar.get("www.hyper.rs", "/guides", "lang").unwrap();
+/// assert!(lang.host_only());
+/// ```
+///
+/// # Eviction
+///
+/// A jar is unbounded by default. Limits can be set on the number of
+/// cookies per registrable domain, the number of cookies overall, and their
+/// total size. When a stored cookie goes over a limit, cookies are evicted
+/// like browsers do: expired cookies first, then those with a lower
+/// `Priority`, then the least recently used.
+///
+/// ```
+/// use cookies::CookieJar;
+/// use url::Url;
+///
+/// let url = Url::parse("https://hyper.rs/").unwrap();
+///
+/// let mut jar = CookieJar::new().max_cookies_per_domain(2);
+/// jar.store_set_cookie(&url, "a=1; Priority=High").unwrap();
+/// jar.store_set_cookie(&url, "b=2").unwrap();
+/// jar.store_set_cookie(&url, "c=3").unwrap();
+///
+/// assert!(jar.get("hyper.rs", "/", "a").is_some());
+/// assert!(jar.get("hyper.rs", "/", "b").is_none());
+/// assert!(jar.get("hyper.rs", "/", "c").is_some());
+/// ```
+///
+/// # Partitions
+///
+/// `Partitioned` cookies are stored in a partition keyed by the top-level
+/// site of the request that set them, as in CHIPS, and are only sent with
+/// requests from the same top-level site. See
+/// `RequestContext::top_level_site`.
+///
+/// ```
+/// use cookies::{CookieJar, RequestContext};
+/// use url::Url;
+///
+/// let widget = Url::parse("https://widget.example/").unwrap();
+/// let embedded = RequestContext::new()
+///     .top_level_site(Url::parse("https://www.hyper.rs/").unwrap());
+///
+/// let mut jar = CookieJar::new();
+/// jar.store_set_cookie_with_context(&widget, "id=1; Secure; Partitioned", &embedded)
+///     .unwrap();
+///
+/// let c = jar.get_partitioned("https://hyper.rs", "widget.example", "/", "id");
+/// assert!(c.is_some());
+///
+/// assert_eq!(jar.cookie_header(&widget, &embedded).unwrap(), "id=1");
+/// assert_eq!(jar.cookie_header(&widget, &RequestContext::new()), None);
+/// ```
+#[derive(Debug, Default)]
+pub struct CookieJar {
+    // Keyed by the domain of the cookies.
+    cookies: BTreeMap<String, Vec<StoredCookie>>,
+    // The embedded list is used if not set.
+    public_suffixes: Option<Arc<PublicSuffixList>>,
+    limits: Limits,
+    usage: Usage,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Limits {
+    per_domain: Option<usize>,
+    per_partition: Option<usize>,
+    total: Option<usize>,
+    bytes: Option<usize>,
+}
+
+/// The number and size of the stored cookies, kept up to date as cookies
+/// are added and removed, so checking the limits doesn't scan the jar.
+#[derive(Debug, Default)]
+struct Usage {
+    count: usize,
+    bytes: usize,
+    // Unpartitioned cookies, by registrable domain.
+    sites: HashMap<String, usize>,
+    partitions: HashMap<String, usize>,
+}
+
+/// A cookie stored in a `CookieJar`.
+///
+/// As a `Cookie`, it has a `Domain` only if it isn't host-only, and an
+/// `Expires` only if it is persistent.
+#[derive(Clone)]
+pub struct StoredCookie {
+    pub(crate) name: String,
+    pub(crate) value: String,
+    pub(crate) domain: String,
+    pub(crate) path: String,
+    pub(crate) expiry: Option<SystemTime>,
+    pub(crate) creation: SystemTime,
+    pub(crate) last_access: AccessTime,
+    pub(crate) host_only: bool,
+    pub(crate) secure: bool,
+    pub(crate) http_only: bool,
+    pub(crate) same_site: Option<SameSite>,
+    pub(crate) priority: Option<Priority>,
+    pub(crate) partition_key: Option<String>,
+}
+
+/// The last access time of a `StoredCookie`, which is updated when it is
+/// sent, through a shared reference to the jar.
+pub(crate) struct AccessTime {
+    // Nanoseconds since the Unix epoch.
+    nanos: AtomicU64,
+}
+
+#[derive(Clone, Copy)]
+enum Scope<'a> {
+    All,
+    Site(&'a str),
+    Partition(&'a str),
+}
+
+// ===== impl CookieJar =====
+
+impl CookieJar {
+    /// Create an empty `CookieJar`.
+    pub fn new() -> CookieJar {
+        CookieJar::default()
+    }
+
+    /// Use a different `PublicSuffixList`, such as a newer one loaded at
+    /// runtime, instead of the embedded one.
+    pub fn with_public_suffix_list(mut self, list: Arc<PublicSuffixList>) -> CookieJar {
+        self.public_suffixes = Some(list);
+        self
+    }
+
+    /// Limit the number of cookies stored for each registrable domain, such
+    /// as `hyper.rs` for both `hyper.rs` and `www.hyper.rs`.
+    ///
+    /// Browsers usually allow around 180.
+    pub fn max_cookies_per_domain(mut self, max: usize) -> CookieJar {
+        self.limits.per_domain = Some(max);
+        self
+    }
+
+    /// Limit the number of cookies stored in each partition.
+    ///
+    /// Partitioned cookies don't count towards `max_cookies_per_domain`.
+    /// Browsers usually allow around 180.
+    pub fn max_cookies_per_partition(mut self, max: usize) -> CookieJar {
+        self.limits.per_partition = Some(max);
+        self
+    }
+
+    /// Limit the number of cookies stored overall.
+    ///
+    /// Browsers usually allow around 3000.
+    pub fn max_cookies(mut self, max: usize) -> CookieJar {
+        self.limits.total = Some(max);
+        self
+    }
+
+    /// Limit the total size of the stored cookies, counting the bytes of
+    /// their names and values.
+    ///
+    /// A cookie bigger than this on its own is rejected.
+    pub fn max_total_bytes(mut self, max: usize) -> CookieJar {
+        self.limits.bytes = Some(max);
+        self
+    }
+
+    /// Store a cookie received in a response to a request for `url`.
+    ///
+    /// A cookie with the same name, domain and path replaces the existing
+    /// one, keeping its creation time. A cookie that has already expired
+    /// removes the existing one.
+    ///
+    /// # Error
+    ///
+    /// Returns an error if `url` has no host, if the `Domain` of the cookie
+    /// doesn't match it, if the `Domain` is a public suffix, or if the cookie
+    /// alone is bigger than `max_total_bytes`. The cookie is ignored in that
+    /// case.
+    ///
+    /// Storing a cookie may evict others, see the limits above.
+    pub fn store(&mut self, url: &Url, cookie: &dyn Cookie) -> Result<(), Error> {
+        self.store_with_context(url, cookie, &RequestContext::new())
+    }
+
+    /// Store a cookie received in a response to a request for `url`, made
+    /// in some context.
+    ///
+    /// A `Partitioned` cookie is stored in the partition of the top-level
+    /// site of the context. Without one, the request is the top-level one,
+    /// and the cookie is stored in the partition of the site of `url`.
+    ///
+    /// # Error
+    ///
+    /// Returns an error as in `store`, or if a `Partitioned` cookie isn't
+    /// `Secure`. For a non-HTTP API (see `RequestContext::http_api`), also
+    /// returns an error if the cookie is `HttpOnly`, or would replace an
+    /// `HttpOnly` cookie, as in RFC 6265 section 5.3 steps 10 and 11.
+    pub fn store_with_context(
+        &mut self,
+        url: &Url,
+        cookie: &dyn Cookie,
+        ctx: &RequestContext,
+    ) -> Result<(), Error> {
+        let now = SystemTime::now();
+        let (domain, host_only) = resolve_domain(url, cookie.domain(), self.public_suffixes())?;
+
+        if !ctx.http_api && cookie.http_only() {
+            return Err(Error::http_only());
+        }
+
+        let partition_key = if cookie.partitioned() {
+            if !cookie.secure() {
+                return Err(Error::not_secure());
+            }
+            self.partition_key(url, ctx)
+        } else {
+            None
+        };
+
+        let expiry = if let Some(max_age) = cookie.max_age() {
+            if max_age == Duration::from_secs(0) {
+                Some(UNIX_EPOCH)
+            } else {
+                Some(util::saturating_add(now, max_age))
+            }
+        } else {
+            cookie.expires()
+        };
+
+        let path = match cookie.path() {
+            Some(path) => path.to_owned(),
+            None => default_path(url).to_owned(),
+        };
+
+        let stored = StoredCookie {
+            name: cookie.name().to_owned(),
+            value: cookie.value().to_owned(),
+            domain,
+            path,
+            expiry,
+            creation: now,
+            last_access: AccessTime::new(now),
+            host_only,
+            secure: cookie.secure(),
+            http_only: cookie.http_only(),
+            same_site: cookie.same_site(),
+            priority: cookie.priority(),
+            partition_key,
+        };
+
+        if self.limits.bytes.is_some_and(|max| stored.size() > max) {
+            return Err(Error::too_long());
+        }
+        if !ctx.http_api {
+            let old = self.find(
+                stored.partition_key.as_deref(),
+                &stored.domain,
+                &stored.path,
+                &stored.name,
+            );
+            if old.is_some_and(|old| old.http_only) {
+                return Err(Error::http_only());
+            }
+        }
+
+        self.put(stored, true, now);
+        Ok(())
+    }
+
+    /// Insert a cookie as it is, such as one imported from a file.
+    ///
+    /// A cookie with the same name, domain, path and partition replaces the
+    /// existing one. A cookie that has already expired removes the existing
+    /// one. Inserting a cookie may evict others, as when storing one.
+    ///
+    /// There is no request to check the cookie against, so its name, value
+    /// and path are trusted, and a file is trusted to only have cookies its
+    /// sites could set. Cookies no site could set are still rejected.
+    ///
+    /// # Error
+    ///
+    /// Returns an error if the cookie isn't host-only and its domain is a
+    /// public suffix, if it is partitioned but not `Secure`, or if it alone
+    /// is bigger than `max_total_bytes`. The cookie is ignored in that case.
+    pub fn insert(&mut self, mut cookie: StoredCookie) -> Result<(), Error> {
+        cookie.domain.make_ascii_lowercase();
+        let domain = &cookie.domain;
+        match crate::parse::validate_domain(domain) {
+            crate::parse::Domain::AsIs => (),
+            crate::parse::Domain::LeadingDot | crate::parse::Domain::Invalid => {
+                return Err(Error::invalid_domain());
+            }
+        }
+        if !cookie.host_only
+            && !is_ip_domain(domain)
+            && self.public_suffixes().is_public_suffix(domain)
+        {
+            return Err(Error::invalid_domain());
+        }
+        if cookie.partition_key.is_some() && !cookie.secure {
+            return Err(Error::not_secure());
+        }
+        if self.limits.bytes.is_some_and(|max| cookie.size() > max) {
+            return Err(Error::too_long());
+        }
+
+        self.put(cookie, false, SystemTime::now());
+        Ok(())
+    }
+
+    /// Parse a `Set-Cookie` header received in a response to a request for
+    /// `url`, and store the cookie.
+    ///
+    /// # Error
+    ///
+    /// Returns an error if the header doesn't parse as a cookie, or if the
+    /// cookie is ignored as in `store`.
+    pub fn store_set_cookie(&mut self, url: &Url, header: &str) -> Result<(), Error> {
+        let cookie = crate::parse(header)?;
+        self.store(url, &cookie)
+    }
+
+    /// Parse a `Set-Cookie` header received in a response to a request for
+    /// `url` made in some context, and store the cookie.
+    ///
+    /// # Error
+    ///
+    /// Returns an error if the header doesn't parse as a cookie, or if the
+    /// cookie is ignored as in `store_with_context`.
+    pub fn store_set_cookie_with_context(
+        &mut self,
+        url: &Url,
+        header: &str,
+        ctx: &RequestContext,
+    ) -> Result<(), Error> {
+        let cookie = crate::parse(header)?;
+        self.store_with_context(url, &cookie, ctx)
+    }
+
+    /// Get the cookies to send with a request for `url`, as in RFC 6265
+    /// section 5.4.
+    ///
+    /// Partitioned cookies are only included from the partition of the
+    /// context. The cookies are ordered as they should be in a `Cookie` header: those
+    /// with longer paths first, then those created earlier. Their last
+    /// access time is updated, which doesn't need a mutable jar, so a jar
+    /// behind a `RwLock` can be read by many threads at once.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use cookies::{CookieJar, RequestContext};
+    /// use url::Url;
+    ///
+    /// let url = Url::parse("https://hyper.rs/guides/client").unwrap();
+    ///
+    /// let mut jar = CookieJar::new();
+    /// jar.store_set_cookie(&url, "a=1; Path=/").unwrap();
+    /// jar.store_set_cookie(&url, "b=2; Path=/guides").unwrap();
+    /// jar.store_set_cookie(&url, "c=3; Path=/blog").unwrap();
+    ///
+    /// let ctx = RequestContext::new();
+    /// assert_eq!(jar.cookie_header(&url, &ctx).unwrap(), "b=2; a=1");
+    /// ```
+    pub fn cookies_for(&self, url: &Url, ctx: &RequestContext) -> Vec<&StoredCookie> {
+        let now = SystemTime::now();
+        let host = match url.host() {
+            Some(host) => host,
+            None => return Vec::new(),
+        };
+        let request_host = canonical_host(&host);
+        let host_is_ip = is_ip(&host);
+        let secure = matches!(url.scheme(), "https" | "wss");
+
+        let partition_key = self.partition_key(url, ctx);
+        let cross_site = match ctx.site_for_cookies {
+            Some(ref site) => match site.host() {
+                Some(site) => self.site_of(&canonical_host(&site)) != self.site_of(&request_host),
+                None => true,
+            },
+            None => false,
+        };
+
+        let mut cookies = self
+            .cookies
+            .iter()
+            .filter(|(domain, _)| domain_match(&request_host, host_is_ip, domain))
+            .flat_map(|(_, cookies)| cookies)
+            .filter(|c| {
+                (!c.host_only || c.domain == request_host)
+                    && path_match(url.path(), &c.path)
+                    && !c.is_expired_at(now)
+                    && (secure || !c.secure)
+                    && (ctx.http_api || !c.http_only)
+                    && (!cross_site || c.sent_cross_site(ctx))
+                    && (c.partition_key.is_none() || c.partition_key == partition_key)
+            })
+            .inspect(|c| c.last_access.set(now))
+            .collect::<Vec<_>>();
+
+        cookies.sort_by(|a, b| {
+            b.path
+                .len()
+                .cmp(&a.path.len())
+                .then(a.creation.cmp(&b.creation))
+        });
+        cookies
+    }
+
+    /// Get the value of the `Cookie` header to send with a request for
+    /// `url`, or `None` if there are no cookies to send.
+    ///
+    /// See `cookies_for`.
+    pub fn cookie_header(&self, url: &Url, ctx: &RequestContext) -> Option<String> {
+        let cookies = self.cookies_for(url, ctx);
+        if cookies.is_empty() {
+            return None;
+        }
+
+        let mut header = String::new();
+        for (i, c) in cookies.into_iter().enumerate() {
+            if i > 0 {
+                header.push_str("; ");
+            }
+            header.push_str(&c.name);
+            header.push('=');
+            header.push_str(&c.value);
+        }
+        Some(header)
+    }
+
+    /// Get the unpartitioned cookie stored with a domain, path and name.
+    pub fn get(&self, domain: &str, path: &str, name: &str) -> Option<&StoredCookie> {
+        self.find(None, domain, path, name)
+    }
+
+    /// Get the cookie stored in a partition with a domain, path and name.
+    ///
+    /// See `StoredCookie::partition_key`.
+    pub fn get_partitioned(
+        &self,
+        partition_key: &str,
+        domain: &str,
+        path: &str,
+        name: &str,
+    ) -> Option<&StoredCookie> {
+        self.find(Some(partition_key), domain, path, name)
+    }
+
+    /// Remove the unpartitioned cookie stored with a domain, path and name.
+    pub fn remove(&mut self, domain: &str, path: &str, name: &str) -> Option<StoredCookie> {
+        self.take(None, domain, path, name)
+    }
+
+    /// Remove all the cookies stored in a partition.
+    pub fn remove_partition(&mut self, partition_key: &str) {
+        self.retain(|c| c.partition_key.as_deref() != Some(partition_key));
+    }
+
+    /// Iterate over all stored cookies, including expired ones that haven't
+    /// been removed yet.
+    pub fn iter(&self) -> impl Iterator<Item = &StoredCookie> {
+        self.cookies.values().flatten()
+    }
+
+    /// Get the number of stored cookies.
+    pub fn len(&self) -> usize {
+        self.usage.count
+    }
+
+    /// Check if there are no stored cookies.
+    pub fn is_empty(&self) -> bool {
+        self.cookies.is_empty()
+    }
+
+    /// Remove all cookies that have expired.
+    pub fn remove_expired(&mut self) {
+        let now = SystemTime::now();
+        self.retain(|c| !c.is_expired_at(now));
+    }
+
+    /// Remove all the session cookies, which are not persistent.
+    pub fn remove_session_cookies(&mut self) {
+        self.retain(StoredCookie::is_persistent);
+    }
+
+    /// Remove all cookies.
+    pub fn clear(&mut self) {
+        self.cookies.clear();
+        self.usage = Usage::default();
+    }
+
+    // private
+
+    fn public_suffixes(&self) -> &PublicSuffixList {
+        public_suffixes(&self.public_suffixes)
+    }
+
+    fn put(&mut self, mut stored: StoredCookie, keep_creation: bool, now: SystemTime) {
+        let list = public_suffixes(&self.public_suffixes);
+        let cookies = self.cookies.entry(stored.domain.clone()).or_default();
+        if let Some(pos) = cookies.iter().position(|old| {
+            old.name == stored.name
+                && old.path == stored.path
+                && old.partition_key == stored.partition_key
+        }) {
+            let old = cookies.remove(pos);
+            self.usage.remove(list, &old);
+            if keep_creation {
+                stored.creation = old.creation;
+            }
+        }
+
+        if !stored.is_expired_at(now) {
+            let domain = stored.domain.clone();
+            let partition_key = stored.partition_key.clone();
+            self.usage.add(list, &stored);
+            cookies.push(stored);
+            self.evict(&domain, partition_key.as_deref(), now);
+        } else if cookies.is_empty() {
+            self.cookies.remove(&stored.domain);
+        }
+    }
+
+    /// Keep only the cookies `keep` returns true for.
+    fn retain(&mut self, mut keep: impl FnMut(&StoredCookie) -> bool) {
+        let list = public_suffixes(&self.public_suffixes);
+        let usage = &mut self.usage;
+        self.cookies.retain(|_, cookies| {
+            cookies.retain(|c| {
+                let kept = keep(c);
+                if !kept {
+                    usage.remove(list, c);
+                }
+                kept
+            });
+            !cookies.is_empty()
+        });
+    }
+
+    fn find(
+        &self,
+        partition_key: Option<&str>,
+        domain: &str,
+        path: &str,
+        name: &str,
+    ) -> Option<&StoredCookie> {
+        self.cookies.get(domain)?.iter().find(|c| {
+            c.path == path && c.name == name && c.partition_key.as_deref() == partition_key
+        })
+    }
+
+    fn take(
+        &mut self,
+        partition_key: Option<&str>,
+        domain: &str,
+        path: &str,
+        name: &str,
+    ) -> Option<StoredCookie> {
+        let cookies = self.cookies.get_mut(domain)?;
+        let pos = cookies.iter().position(|c| {
+            c.path == path && c.name == name && c.partition_key.as_deref() == partition_key
+        })?;
+        let removed = cookies.remove(pos);
+        if cookies.is_empty() {
+            self.cookies.remove(domain);
+        }
+        self.usage
+            .remove(public_suffixes(&self.public_suffixes), &removed);
+        Some(removed)
+    }
+
+    /// Get the partition key of a request, which is the scheme and
+    /// registrable domain of its top-level site, or of its own URL if it
+    /// is the top-level request.
+    fn partition_key(&self, url: &Url, ctx: &RequestContext) -> Option<String> {
+        let site = ctx.top_level_site.as_ref().unwrap_or(url);
+        let host = canonical_host(&site.host()?);
+        Some(format!("{}://{}", site.scheme(), self.site_of(&host)))
+    }
+
+    /// Get the registrable domain cookies for a domain are limited by.
+    fn site_of<'a>(&self, domain: &'a str) -> &'a str {
+        site_of(self.public_suffixes(), domain)
+    }
+
+    /// Enforce the limits after a cookie was stored for `domain`, in a
+    /// partition if set.
+    fn evict(&mut self, domain: &str, partition_key: Option<&str>, now: SystemTime) {
+        let limits = self.limits;
+        match (partition_key, limits.per_partition, limits.per_domain) {
+            (Some(key), Some(max), _) => {
+                let count = self.usage.partitions.get(key).copied().unwrap_or(0);
+                if count > max {
+                    self.evict_over(Scope::Partition(key), count, max, |_| 1, now);
+                }
+            }
+            (None, _, Some(max)) => {
+                let site = self.site_of(domain).to_owned();
+                let count = self.usage.sites.get(&site).copied().unwrap_or(0);
+                if count > max {
+                    self.evict_over(Scope::Site(&site), count, max, |_| 1, now);
+                }
+            }
+            _ => (),
+        }
+        if let Some(max) = limits.total {
+            if self.usage.count > max {
+                self.evict_over(Scope::All, self.usage.count, max, |_| 1, now);
+            }
+        }
+        if let Some(max) = limits.bytes {
+            if self.usage.bytes > max {
+                self.evict_over(Scope::All, self.usage.bytes, max, StoredCookie::size, now);
+            }
+        }
+    }
+
+    /// Evict cookies in a scope until their total `size` is at most `max`.
+    fn evict_over(
+        &mut self,
+        scope: Scope<'_>,
+        mut total: usize,
+        max: usize,
+        size: fn(&StoredCookie) -> usize,
+        now: SystemTime,
+    ) {
+        // Only the domains under a site need their site looked up.
+        let domain_in_scope = |domain: &str| match scope {
+            Scope::Site(site) => domain_match(domain, false, site) && self.site_of(domain) == site,
+            Scope::All | Scope::Partition(_) => true,
+        };
+        let in_scope = |c: &&StoredCookie| match scope {
+            Scope::All => true,
+            Scope::Site(_) => c.partition_key.is_none(),
+            Scope::Partition(key) => c.partition_key.as_deref() == Some(key),
+        };
+
+        // Expired first, then lowest priority, then least recently used.
+        let mut victims = self
+            .cookies
+            .iter()
+            .filter(|(domain, _)| domain_in_scope(domain))
+            .flat_map(|(_, cookies)| cookies)
+            .filter(in_scope)
+            .map(|c| {
+                let key = (
+                    !c.is_expired_at(now),
+                    c.effective_priority(),
+                    c.last_access.get(),
+                );
+                let id = (c.partition_key.clone(), c.domain.clone(), c.path.clone());
+                (key, id, c.name.clone())
+            })
+            .collect::<Vec<_>>();
+        victims.sort_by_key(|victim| victim.0);
+
+        for (_, (partition_key, domain, path), name) in victims {
+            if total <= max {
+                break;
+            }
+            let evicted = self.take(partition_key.as_deref(), &domain, &path, &name);
+            if let Some(evicted) = evicted {
+                total -= size(&evicted);
+            }
+        }
+    }
+}
+
+// ===== impl Usage =====
+
+impl Usage {
+    fn add(&mut self, list: &PublicSuffixList, cookie: &StoredCookie) {
+        self.count += 1;
+        self.bytes += cookie.size();
+        let counts = match cookie.partition_key {
+            Some(ref key) => self.partitions.entry(key.clone()),
+            None => self.sites.entry(site_of(list, &cookie.domain).to_owned()),
+        };
+        *counts.or_default() += 1;
+    }
+
+    fn remove(&mut self, list: &PublicSuffixList, cookie: &StoredCookie) {
+        self.count -= 1;
+        self.bytes -= cookie.size();
+        let (counts, key) = match cookie.partition_key {
+            Some(ref key) => (&mut self.partitions, &key[..]),
+            None => (&mut self.sites, site_of(list, &cookie.domain)),
+        };
+        if let Some(count) = counts.get_mut(key) {
+            *count -= 1;
+            if *count == 0 {
+                counts.remove(key);
+            }
+        }
+    }
+}
+
+// ===== impl StoredCookie =====
+
+impl StoredCookie {
+    /// Create a session cookie, for a domain and its subdomains, with no
+    /// other attributes.
+    ///
+    /// The fields are expected to be valid and canonical already, such as
+    /// when imported from another store.
+    pub(crate) fn new(name: String, value: String, domain: String, path: String) -> StoredCookie {
+        let now = SystemTime::now();
+        StoredCookie {
+            name,
+            value,
+            domain,
+            path,
+            expiry: None,
+            creation: now,
+            last_access: AccessTime::new(now),
+            host_only: false,
+            secure: false,
+            http_only: false,
+            same_site: None,
+            priority: None,
+            partition_key: None,
+        }
+    }
+
+    /// Get the host or domain this cookie is stored for.
+    ///
+    /// For a host-only cookie, this is the host of the request that set it.
+    pub fn host(&self) -> &str {
+        &self.domain
+    }
+
+    /// Get if this cookie is only sent to the exact host that set it.
+    pub fn host_only(&self) -> bool {
+        self.host_only
+    }
+
+    /// Get if this cookie is persistent, or should be removed at the end of
+    /// the session.
+    pub fn is_persistent(&self) -> bool {
+        self.expiry.is_some()
+    }
+
+    /// Get if this cookie has expired.
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(SystemTime::now())
+    }
+
+    /// Get when this cookie was first stored.
+    ///
+    /// This is kept when the cookie is replaced by a newer one.
+    pub fn creation_time(&self) -> SystemTime {
+        self.creation
+    }
+
+    /// Get when this cookie was last stored or sent.
+    pub fn last_access_time(&self) -> SystemTime {
+        self.last_access.get()
+    }
+
+    /// Get the `Priority` this cookie is evicted by, which is `MEDIUM` if
+    /// not set.
+    pub fn effective_priority(&self) -> Priority {
+        self.priority.unwrap_or(Priority::MEDIUM)
+    }
+
+    /// Check the `SameSite` of this cookie for a cross-site request.
+    ///
+    /// Cookies without a `SameSite` are sent, as in RFC 6265.
+    fn sent_cross_site(&self, ctx: &RequestContext) -> bool {
+        match self.same_site {
+            Some(SameSite::STRICT) => false,
+            Some(SameSite::LAX) => ctx.top_level_navigation && ctx.safe_method,
+            _ => true,
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.name.len() + self.value.len()
+    }
+
+    /// Get the key of the partition this cookie is stored in, if it is
+    /// partitioned.
+    ///
+    /// This is the scheme and registrable domain of the top-level site, such
+    /// as `https://hyper.rs`.
+    pub fn partition_key(&self) -> Option<&str> {
+        self.partition_key.as_deref()
+    }
+
+    fn is_expired_at(&self, now: SystemTime) -> bool {
+        match self.expiry {
+            Some(expiry) => expiry <= now,
+            None => false,
+        }
+    }
+}
+
+impl Cookie for StoredCookie {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn value(&self) -> &str {
+        &self.value
+    }
+
+    fn domain(&self) -> Option<&str> {
+        if self.host_only {
+            None
+        } else {
+            Some(&self.domain)
+        }
+    }
+
+    fn path(&self) -> Option<&str> {
+        Some(&self.path)
+    }
+
+    fn max_age(&self) -> Option<Duration> {
+        None
+    }
+
+    fn expires(&self) -> Option<SystemTime> {
+        self.expiry
+    }
+
+    fn http_only(&self) -> bool {
+        self.http_only
+    }
+
+    fn secure(&self) -> bool {
+        self.secure
+    }
+
+    fn same_site(&self) -> Option<SameSite> {
+        self.same_site
+    }
+
+    fn priority(&self) -> Option<Priority> {
+        self.priority
+    }
+
+    fn partitioned(&self) -> bool {
+        self.partition_key.is_some()
+    }
+}
+
+impl Sealed for StoredCookie {}
+
+impl fmt::Debug for StoredCookie {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        util::debug(self, f)
+    }
+}
+
+impl fmt::Display for StoredCookie {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        util::display(self, f)
+    }
+}
+
+// ===== impl AccessTime =====
+
+impl AccessTime {
+    pub(crate) fn new(time: SystemTime) -> AccessTime {
+        let access = AccessTime {
+            nanos: AtomicU64::new(0),
+        };
+        access.set(time);
+        access
+    }
+
+    pub(crate) fn get(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_nanos(self.nanos.load(Ordering::Relaxed))
+    }
+
+    pub(crate) fn set(&self, time: SystemTime) {
+        let nanos = time.duration_since(UNIX_EPOCH).map_or(0, |since| {
+            u64::try_from(since.as_nanos()).unwrap_or(u64::MAX)
+        });
+        self.nanos.store(nanos, Ordering::Relaxed);
+    }
+}
+
+impl Clone for AccessTime {
+    fn clone(&self) -> AccessTime {
+        AccessTime {
+            nanos: AtomicU64::new(self.nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+// ===== RFC 6265 algorithms =====
+
+fn canonical_host(host: &Host<&str>) -> String {
+    match *host {
+        Host::Domain(domain) => domain.to_ascii_lowercase(),
+        Host::Ipv4(ref ip) => ip.to_string(),
+        Host::Ipv6(ref ip) => format!("[{}]", ip),
+    }
+}
+
+fn is_ip(host: &Host<&str>) -> bool {
+    match *host {
+        Host::Domain(_) => false,
+        Host::Ipv4(_) | Host::Ipv6(_) => true,
+    }
+}
+
+fn public_suffixes(list: &Option<Arc<PublicSuffixList>>) -> &PublicSuffixList {
+    match *list {
+        Some(ref list) => list,
+        None => PublicSuffixList::embedded(),
+    }
+}
+
+/// Get the registrable domain cookies for a domain are limited by.
+fn site_of<'a>(list: &PublicSuffixList, domain: &'a str) -> &'a str {
+    if is_ip_domain(domain) {
+        return domain;
+    }
+    list.registrable_domain(domain).unwrap_or(domain)
+}
+
+/// Check if a stored domain is an IP address, which has no site.
+fn is_ip_domain(domain: &str) -> bool {
+    domain.starts_with('[') || domain.parse::<Ipv4Addr>().is_ok()
+}
+
+/// Get the domain to store a cookie for, and if it is host-only, as in RFC
+/// 6265 section 5.3 steps 4 to 6.
+pub(crate) fn resolve_domain(
+    url: &Url,
+    domain: Option<&str>,
+    public_suffixes: &PublicSuffixList,
+) -> Result<(String, bool), Error> {
+    let host = url.host().ok_or_else(Error::invalid_domain)?;
+    let request_host = canonical_host(&host);
+
+    let domain = match domain {
+        Some(domain) => domain.to_ascii_lowercase(),
+        None => return Ok((request_host, true)),
+    };
+
+    if !is_ip(&host) && public_suffixes.is_public_suffix(&domain) {
+        // A public suffix is only allowed for the exact host, such as for a
+        // site hosted directly on one, and then it is host-only.
+        return if domain == request_host {
+            Ok((request_host, true))
+        } else {
+            Err(Error::invalid_domain())
+        };
+    }
+
+    if !domain_match(&request_host, is_ip(&host), &domain) {
+        return Err(Error::invalid_domain());
+    }
+
+    Ok((domain, false))
+}
+
+/// Domain matching, as in RFC 6265 section 5.1.3.
+pub(crate) fn domain_match(host: &str, host_is_ip: bool, domain: &str) -> bool {
+    if host == domain {
+        return true;
+    }
+
+    !host_is_ip
+        && host.len() > domain.len()
+        && host.ends_with(domain)
+        && host.as_bytes()[host.len() - domain.len() - 1] == b'.'
+}
+
+/// Path matching, as in RFC 6265 section 5.1.4.
+pub(crate) fn path_match(request_path: &str, cookie_path: &str) -> bool {
+    let request_path = if request_path.starts_with('/') {
+        request_path
+    } else {
+        "/"
+    };
+
+    request_path.starts_with(cookie_path)
+        && (request_path.len() == cookie_path.len()
+            || cookie_path.ends_with('/')
+            || request_path.as_bytes()[cookie_path.len()] == b'/')
+}
+
+/// The default-path of a URL, as in RFC 6265 section 5.1.4.
+pub(crate) fn default_path(url: &Url) -> &str {
+    let path = url.path();
+    if !path.starts_with('/') {
+        return "/";
+    }
+
+    match path.rfind('/') {
+        Some(0) | None => "/",
+        Some(i) => &path[..i],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Builder;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    #[test]
+    fn host_only() {
+        let mut jar = CookieJar::new();
+        jar.store_set_cookie(&url("http://Hyper.RS/"), "foo=bar")
+            .unwrap();
+
+        let c = jar.get("hyper.rs", "/", "foo").expect("stored");
+        assert!(c.host_only());
+        assert_eq!(c.host(), "hyper.rs");
+        assert_eq!(c.domain(), None);
+    }
+
+    #[test]
+    fn domain_cookie() {
+        let mut jar = CookieJar::new();
+        jar.store_set_cookie(&url("http://www.hyper.rs/"), "foo=bar; Domain=HYPER.rs")
+            .unwrap();
+
+        let c = jar.get("hyper.rs", "/", "foo").expect("stored");
+        assert!(!c.host_only());
+        assert_eq!(c.domain(), Some("hyper.rs"));
+    }
+
+    #[test]
+    fn domain_mismatch() {
+        let mut jar = CookieJar::new();
+        jar.store_set_cookie(&url("http://hyper.rs/"), "foo=bar; Domain=rust-lang.org")
+            .expect_err("other domain");
+        jar.store_set_cookie(&url("http://hyper.rs/"), "foo=bar; Domain=www.hyper.rs")
+            .expect_err("subdomain");
+        jar.store_set_cookie(&url("http://notHyper.rs/"), "foo=bar; Domain=hyper.rs")
+            .expect_err("not on a label boundary");
+        jar.store_set_cookie(&url("http://127.0.0.1/"), "foo=bar; Domain=0.0.1")
+            .expect_err("IP address");
+        assert!(jar.is_empty());
+
+        jar.store_set_cookie(&url("http://127.0.0.1/"), "foo=bar; Domain=127.0.0.1")
+            .expect("same IP address");
+    }
+
+    #[test]
+    fn public_suffix() {
+        let mut jar = CookieJar::new();
+        jar.store_set_cookie(&url("http://www.example.co.uk/"), "foo=bar; Domain=co.uk")
+            .expect_err("supercookie");
+        jar.store_set_cookie(&url("http://hyper.rs/"), "foo=bar; Domain=rs")
+            .expect_err("supercookie");
+        assert!(jar.is_empty());
+
+        jar.store_set_cookie(&url("http://github.io/"), "foo=bar; Domain=github.io")
+            .expect("public suffix of the exact host");
+        assert!(jar.get("github.io", "/", "foo").unwrap().host_only());
+    }
+
+    #[test]
+    fn custom_public_suffix_list() {
+        let list = PublicSuffixList::parse("rs\nhyper.rs\n");
+        let mut jar = CookieJar::new().with_public_suffix_list(Arc::new(list));
+
+        jar.store_set_cookie(&url("http://docs.hyper.rs/"), "foo=bar; Domain=hyper.rs")
+            .expect_err("public suffix in custom list");
+        jar.store_set_cookie(&url("http://docs.co.uk/"), "foo=bar; Domain=docs.co.uk")
+            .expect("not in custom list");
+    }
+
+    #[test]
+    fn default_paths() {
+        assert_eq!(default_path(&url("http://hyper.rs")), "/");
+        assert_eq!(default_path(&url("http://hyper.rs/")), "/");
+        assert_eq!(default_path(&url("http://hyper.rs/guides")), "/");
+        assert_eq!(default_path(&url("http://hyper.rs/guides/")), "/guides");
+        assert_eq!(
+            default_path(&url("http://hyper.rs/guides/client?a=/b")),
+            "/guides"
+        );
+    }
+
+    #[test]
+    fn replace_keeps_creation_time() {
+        let u = url("http://hyper.rs/");
+        let mut jar = CookieJar::new();
+        jar.store_set_cookie(&u, "foo=1").unwrap();
+        let created = jar.get("hyper.rs", "/", "foo").unwrap().creation_time();
+
+        jar.store_set_cookie(&u, "foo=2; HttpOnly").unwrap();
+
+        assert_eq!(jar.len(), 1);
+        let c = jar.get("hyper.rs", "/", "foo").unwrap();
+        assert_eq!(c.value(), "2");
+        assert!(c.http_only());
+        assert_eq!(c.creation_time(), created);
+
+        jar.store_set_cookie(&u, "foo=3; Path=/other").unwrap();
+        assert_eq!(jar.len(), 2, "different path is a different cookie");
+    }
+
+    #[test]
+    fn expiry() {
+        let u = url("http://hyper.rs/");
+        let mut jar = CookieJar::new();
+
+        jar.store_set_cookie(&u, "session=1").unwrap();
+        jar.store_set_cookie(&u, "persistent=1; Max-Age=100")
+            .unwrap();
+        assert!(!jar.get("hyper.rs", "/", "session").unwrap().is_persistent());
+        assert!(jar
+            .get("hyper.rs", "/", "persistent")
+            .unwrap()
+            .is_persistent());
+
+        jar.store_set_cookie(&u, "persistent=; Max-Age=0").unwrap();
+        assert!(jar.get("hyper.rs", "/", "persistent").is_none(), "removed");
+
+        let expired = Builder::new("old", "1")
+            .expires(UNIX_EPOCH + Duration::from_secs(1))
+            .build()
+            .unwrap();
+        jar.store(&u, &expired).unwrap();
+        assert!(jar.get("hyper.rs", "/", "old").is_none(), "not stored");
+
+        jar.remove_session_cookies();
+        assert!(jar.is_empty());
+
+        jar.store_set_cookie(&u, "forever=1; Max-Age=1000000000000")
+            .unwrap();
+        assert_eq!(
+            jar.get("hyper.rs", "/", "forever").unwrap().expires(),
+            Some(UNIX_EPOCH + Duration::from_secs(253_402_300_799)),
+            "clamped to the year 9999"
+        );
+    }
+
+    #[test]
+    fn paths() {
+        assert!(path_match("/", "/"));
+        assert!(path_match("/guides", "/guides"));
+        assert!(path_match("/guides/", "/guides"));
+        assert!(path_match("/guides/client", "/guides/"));
+        assert!(!path_match("/guidesx", "/guides"));
+        assert!(!path_match("/", "/guides"));
+    }
+
+    #[test]
+    fn cookies_for_matches() {
+        let mut jar = CookieJar::new();
+        jar.store_set_cookie(&url("https://www.hyper.rs/"), "host=1")
+            .unwrap();
+        jar.store_set_cookie(&url("https://www.hyper.rs/"), "domain=1; Domain=hyper.rs")
+            .unwrap();
+        jar.store_set_cookie(&url("https://www.hyper.rs/"), "deep=1; Path=/guides")
+            .unwrap();
+        jar.store_set_cookie(&url("https://www.hyper.rs/"), "secure=1; Secure")
+            .unwrap();
+        jar.store_set_cookie(&url("https://www.hyper.rs/"), "http=1; HttpOnly")
+            .unwrap();
+
+        let ctx = RequestContext::new();
+        let header = |jar: &mut CookieJar, u: &str, ctx: &RequestContext| {
+            jar.cookie_header(&url(u), ctx).unwrap_or_default()
+        };
+
+        assert_eq!(
+            header(&mut jar, "https://www.hyper.rs/guides/client", &ctx),
+            "deep=1; host=1; domain=1; secure=1; http=1"
+        );
+        assert_eq!(
+            header(&mut jar, "http://www.hyper.rs/", &ctx),
+            "host=1; domain=1; http=1"
+        );
+        assert_eq!(
+            header(&mut jar, "https://docs.hyper.rs/", &ctx),
+            "domain=1",
+            "host-only not sent to subdomain"
+        );
+        assert_eq!(header(&mut jar, "https://rust-lang.org/", &ctx), "");
+
+        let script = RequestContext::new().http_api(false);
+        assert_eq!(
+            header(&mut jar, "https://www.hyper.rs/", &script),
+            "host=1; domain=1; secure=1"
+        );
+    }
+
+    #[test]
+    fn script_cant_set_http_only() {
+        let u = url("https://www.hyper.rs/");
+        let script = RequestContext::new().http_api(false);
+        let mut jar = CookieJar::new();
+        jar.store_set_cookie(&u, "http=1; HttpOnly").unwrap();
+
+        jar.store_set_cookie_with_context(&u, "new=1; HttpOnly", &script)
+            .expect_err("HttpOnly from script");
+        assert!(jar.get("www.hyper.rs", "/", "new").is_none());
+
+        jar.store_set_cookie_with_context(&u, "http=2", &script)
+            .expect_err("replace HttpOnly from script");
+        assert_eq!(jar.get("www.hyper.rs", "/", "http").unwrap().value(), "1");
+
+        jar.store_set_cookie_with_context(&u, "plain=1", &script)
+            .unwrap();
+        jar.store_set_cookie(&u, "http=2").unwrap();
+        assert_eq!(jar.get("www.hyper.rs", "/", "http").unwrap().value(), "2");
+    }
+
+    #[test]
+    fn cookies_for_same_site() {
+        let u = url("https://hyper.rs/");
+        let mut jar = CookieJar::new();
+        jar.store_set_cookie(&u, "unset=1").unwrap();
+        jar.store_set_cookie(&u, "none=1; SameSite=None; Secure")
+            .unwrap();
+        jar.store_set_cookie(&u, "lax=1; SameSite=Lax").unwrap();
+        jar.store_set_cookie(&u, "strict=1; SameSite=Strict")
+            .unwrap();
+
+        let same_site = RequestContext::new().site_for_cookies(url("https://www.hyper.rs/"));
+        assert_eq!(
+            jar.cookie_header(&u, &same_site).unwrap(),
+            "unset=1; none=1; lax=1; strict=1"
+        );
+
+        let cross_site = RequestContext::new().site_for_cookies(url("https://rust-lang.org/"));
+        assert_eq!(
+            jar.cookie_header(&u, &cross_site).unwrap(),
+            "unset=1; none=1"
+        );
+
+        let navigation = cross_site.clone().top_level_navigation(true);
+        assert_eq!(
+            jar.cookie_header(&u, &navigation).unwrap(),
+            "unset=1; none=1; lax=1"
+        );
+
+        let post = navigation.method("POST");
+        assert_eq!(jar.cookie_header(&u, &post).unwrap(), "unset=1; none=1");
+    }
+
+    #[test]
+    fn cookies_for_updates_last_access() {
+        let u = url("http://hyper.rs/");
+        let mut jar = CookieJar::new();
+        jar.store_set_cookie(&u, "foo=bar").unwrap();
+        let stored = jar.get("hyper.rs", "/", "foo").unwrap().last_access_time();
+
+        std::thread::sleep(Duration::from_millis(10));
+        jar.cookies_for(&u, &RequestContext::new());
+
+        assert!(jar.get("hyper.rs", "/", "foo").unwrap().last_access_time() > stored);
+    }
+
+    #[test]
+    fn partitioned() {
+        let widget = url("https://widget.example/");
+        let in_hyper = RequestContext::new().top_level_site(url("https://www.hyper.rs/"));
+        let in_rust = RequestContext::new().top_level_site(url("https://rust-lang.org/"));
+
+        let mut jar = CookieJar::new();
+        jar.store_set_cookie_with_context(&widget, "id=1; Secure; Partitioned", &in_hyper)
+            .unwrap();
+        jar.store_set_cookie_with_context(&widget, "id=2; Secure; Partitioned", &in_rust)
+            .unwrap();
+        jar.store_set_cookie(&widget, "id=3; Secure").unwrap();
+        assert_eq!(jar.len(), 3);
+
+        let c = jar
+            .get_partitioned("https://hyper.rs", "widget.example", "/", "id")
+            .unwrap();
+        assert_eq!(c.value(), "1");
+        assert!(c.partitioned());
+        assert_eq!(jar.get("widget.example", "/", "id").unwrap().value(), "3");
+
+        assert_eq!(jar.cookie_header(&widget, &in_hyper).unwrap(), "id=1; id=3");
+        assert_eq!(jar.cookie_header(&widget, &in_rust).unwrap(), "id=2; id=3");
+        assert_eq!(
+            jar.cookie_header(&widget, &RequestContext::new()).unwrap(),
+            "id=3",
+            "unpartitioned request"
+        );
+
+        jar.remove_partition("https://hyper.rs");
+        assert_eq!(jar.len(), 2);
+    }
+
+    #[test]
+    fn partitioned_top_level() {
+        let widget = url("https://www.widget.example/");
+        let in_hyper = RequestContext::new().top_level_site(url("https://hyper.rs/"));
+
+        // Without a top-level site, the request is the top-level one.
+        let mut jar = CookieJar::new();
+        jar.store_set_cookie(&widget, "id=1; Secure; Partitioned")
+            .unwrap();
+
+        let c = jar
+            .get_partitioned("https://widget.example", "www.widget.example", "/", "id")
+            .expect("partitioned by its own site");
+        assert_eq!(c.partition_key(), Some("https://widget.example"));
+        assert!(jar.get("www.widget.example", "/", "id").is_none());
+
+        assert_eq!(
+            jar.cookie_header(&widget, &RequestContext::new()).unwrap(),
+            "id=1"
+        );
+        assert_eq!(
+            jar.cookie_header(&widget, &in_hyper),
+            None,
+            "other partition"
+        );
+    }
+
+    #[test]
+    fn partitioned_requires_secure() {
+        let in_hyper = RequestContext::new().top_level_site(url("https://hyper.rs/"));
+        let mut jar = CookieJar::new();
+        jar.store_set_cookie_with_context(
+            &url("https://widget.example/"),
+            "id=1; Partitioned",
+            &in_hyper,
+        )
+        .expect_err("not Secure");
+    }
+
+    #[test]
+    fn evict_per_partition() {
+        let widget = url("https://widget.example/");
+        let in_hyper = RequestContext::new().top_level_site(url("https://hyper.rs/"));
+        let mut jar = CookieJar::new()
+            .max_cookies_per_partition(1)
+            .max_cookies_per_domain(1);
+
+        jar.store_set_cookie(&widget, "a=1; Secure").unwrap();
+        jar.store_set_cookie_with_context(&widget, "b=1; Secure; Partitioned", &in_hyper)
+            .unwrap();
+        jar.store_set_cookie_with_context(&widget, "c=1; Secure; Partitioned", &in_hyper)
+            .unwrap();
+
+        let names = jar.iter().map(|c| c.name()).collect::<Vec<_>>();
+        assert_eq!(names, ["a", "c"]);
+    }
+
+    #[test]
+    fn evict_per_domain() {
+        let mut jar = CookieJar::new().max_cookies_per_domain(2);
+        jar.store_set_cookie(&url("http://a.hyper.rs/"), "a=1")
+            .unwrap();
+        jar.store_set_cookie(&url("http://b.hyper.rs/"), "b=1")
+            .unwrap();
+        jar.store_set_cookie(&url("http://rust-lang.org/"), "c=1")
+            .unwrap();
+        jar.store_set_cookie(&url("http://hyper.rs/"), "d=1")
+            .unwrap();
+
+        assert_eq!(jar.len(), 3);
+        assert!(jar.get("a.hyper.rs", "/", "a").is_none(), "least recent");
+        assert!(jar.get("rust-lang.org", "/", "c").is_some(), "other domain");
+    }
+
+    #[test]
+    fn evict_total() {
+        let mut jar = CookieJar::new().max_cookies(2);
+        jar.store_set_cookie(&url("http://hyper.rs/"), "a=1; Priority=High")
+            .unwrap();
+        jar.store_set_cookie(&url("http://rust-lang.org/"), "b=1; Priority=Low")
+            .unwrap();
+        jar.store_set_cookie(&url("http://crates.io/"), "c=1")
+            .unwrap();
+        jar.store_set_cookie(&url("http://docs.rs/"), "d=1")
+            .unwrap();
+
+        let names = jar.iter().map(|c| c.name()).collect::<Vec<_>>();
+        assert_eq!(names, ["d", "a"], "low, then medium evicted before high");
+    }
+
+    #[test]
+    fn evict_expired_first() {
+        let u = url("http://hyper.rs/");
+        let mut jar = CookieJar::new().max_cookies(2);
+        jar.store_set_cookie(&u, "a=1").unwrap();
+        jar.store_set_cookie(&u, "b=1; Max-Age=100; Priority=High")
+            .unwrap();
+        jar.cookies.get_mut("hyper.rs").unwrap()[1].expiry = Some(UNIX_EPOCH);
+
+        jar.store_set_cookie(&u, "c=1").unwrap();
+
+        assert!(jar.get("hyper.rs", "/", "a").is_some());
+        assert!(jar.get("hyper.rs", "/", "b").is_none(), "expired");
+    }
+
+    #[test]
+    fn evict_bytes() {
+        let u = url("http://hyper.rs/");
+        let mut jar = CookieJar::new().max_total_bytes(10);
+        jar.store_set_cookie(&u, "a=1234").unwrap();
+        jar.store_set_cookie(&u, "b=1234").unwrap();
+        assert_eq!(jar.len(), 2);
+
+        jar.store_set_cookie(&u, "c=1").unwrap();
+        assert_eq!(jar.len(), 2);
+        assert!(jar.get("hyper.rs", "/", "a").is_none());
+
+        jar.store_set_cookie(&u, "d=12345678910")
+            .expect_err("too large for the jar on its own");
+        assert_eq!(jar.len(), 2, "nothing evicted for it");
+    }
+
+    #[test]
+    fn evict_least_recently_used() {
+        let u = url("http://hyper.rs/a");
+        let mut jar = CookieJar::new().max_cookies_per_domain(2);
+        jar.store_set_cookie(&u, "a=1; Path=/a").unwrap();
+        jar.store_set_cookie(&u, "b=1; Path=/b").unwrap();
+
+        // Sending `a` makes `b` the least recently used.
+        assert_eq!(
+            jar.cookie_header(&u, &RequestContext::new()).unwrap(),
+            "a=1"
+        );
+        jar.store_set_cookie(&u, "c=1").unwrap();
+
+        assert!(jar.get("hyper.rs", "/a", "a").is_some());
+        assert!(jar.get("hyper.rs", "/b", "b").is_none(), "least recent");
+    }
+
+    #[test]
+    fn usage_tracks_changes() {
+        let widget = url("https://widget.example/");
+        let in_hyper = RequestContext::new().top_level_site(url("https://hyper.rs/"));
+        let mut jar = CookieJar::new();
+        jar.store_set_cookie(&url("http://a.hyper.rs/"), "a=1; Max-Age=100")
+            .unwrap();
+        jar.store_set_cookie(&url("http://b.hyper.rs/"), "b=12")
+            .unwrap();
+        jar.store_set_cookie(&url("http://b.hyper.rs/"), "b=123")
+            .unwrap();
+        jar.store_set_cookie_with_context(&widget, "c=1; Secure; Partitioned", &in_hyper)
+            .unwrap();
+
+        assert_eq!(jar.len(), 3);
+        assert_eq!(jar.usage.bytes, 2 + 4 + 2);
+        assert_eq!(jar.usage.sites["hyper.rs"], 2);
+        assert_eq!(jar.usage.partitions["https://hyper.rs"], 1);
+
+        jar.remove_session_cookies();
+        assert_eq!(jar.len(), 1);
+        assert_eq!(jar.usage.bytes, 2);
+        assert!(jar.usage.partitions.is_empty());
+
+        jar.store_set_cookie(&url("http://a.hyper.rs/"), "a=1; Max-Age=0")
+            .unwrap();
+        assert!(jar.is_empty());
+        assert_eq!(jar.usage.bytes, 0);
+        assert!(jar.usage.sites.is_empty());
+    }
+
+    #[test]
+    fn insert_checks() {
+        let mut jar = CookieJar::new().max_total_bytes(10);
+        let cookie =
+            |domain: &str| StoredCookie::new("a".into(), "1".into(), domain.into(), "/".into());
+
+        jar.insert(cookie("com")).expect_err("supercookie");
+        jar.insert(cookie(".hyper.rs")).expect_err("leading dot");
+        let mut partitioned = cookie("hyper.rs");
+        partitioned.partition_key = Some("https://hyper.rs".into());
+        jar.insert(partitioned)
+            .expect_err("partitioned, not Secure");
+        let mut big = cookie("hyper.rs");
+        big.value = "12345678910".into();
+        jar.insert(big).expect_err("too large for the jar");
+        assert!(jar.is_empty());
+
+        let mut host_only = cookie("github.io");
+        host_only.host_only = true;
+        jar.insert(host_only)
+            .expect("public suffix of the exact host");
+        jar.insert(cookie("127.0.0.1")).expect("IP address");
+        jar.insert(cookie("Hyper.RS")).expect("domain cookie");
+        assert!(jar.get("hyper.rs", "/", "a").is_some(), "lowercased");
+    }
+
+    #[test]
+    fn no_host() {
+        let mut jar = CookieJar::new();
+        jar.store_set_cookie(&url("data:text/plain,hello"), "foo=bar")
+            .expect_err("no host");
+    }
+}
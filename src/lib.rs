@@ -12,19 +12,72 @@
 //! a cookie with a single `struct`, the *concept* of a cookie is instead
 //! exposed as a `trait`.
 
+use std::borrow::Cow;
 use std::fmt;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
+
+#[cfg(feature = "jar")]
+pub mod binarycookies;
+#[cfg(feature = "chromium")]
+pub mod chromium;
+pub mod chunk;
+pub mod codec;
+#[cfg(all(feature = "serde", feature = "jar"))]
+pub mod devtools;
+#[cfg(feature = "firefox")]
+pub mod firefox;
+#[cfg(all(feature = "serde", feature = "jar"))]
+pub mod har;
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(feature = "jar")]
+pub mod netscape;
+#[cfg(feature = "reqwest")]
+pub mod reqwest;
+#[cfg(feature = "tower")]
+pub mod tower;
+#[cfg(feature = "ureq")]
+pub mod ureq;
 
 mod build;
+#[cfg(any(feature = "reqwest", feature = "tower", feature = "ureq"))]
+mod client;
+#[cfg(feature = "jar")]
+mod context;
 mod error;
+#[cfg(feature = "jar")]
+mod jar;
+mod owned;
 mod parse;
+mod policy;
+#[cfg(feature = "jar")]
+mod psl;
+mod serialize;
+#[cfg(feature = "jar")]
+mod shared;
+#[cfg(any(feature = "firefox", feature = "chromium"))]
+mod sqlite;
 mod util;
 
 pub use self::build::Builder;
+#[cfg(feature = "jar")]
+pub use self::context::RequestContext;
 pub use self::error::Error;
-pub use self::parse::parse;
-pub use self::util::SameSite;
+#[cfg(feature = "jar")]
+pub use self::jar::{CookieJar, StoredCookie};
+pub use self::owned::OwnedCookie;
+#[cfg(feature = "jar")]
+pub use self::parse::parse_for_url;
+pub use self::parse::{parse, parse_pairs};
+pub use self::policy::Policy;
+#[cfg(feature = "jar")]
+pub use self::psl::{is_public_suffix, registrable_domain, PublicSuffixList};
+pub use self::serialize::{Attribute, DisplayWith, Expiry, SerializeOptions};
+#[cfg(feature = "jar")]
+pub use self::shared::SharedCookieJar;
+pub use self::util::{serialized_len, write_set_cookie, Priority, SameSite};
 
+use self::codec::Codec;
 use self::sealed::Sealed;
 
 /// Cookies in this crate implement this trait.
@@ -44,6 +97,11 @@ pub trait Cookie: fmt::Debug + fmt::Display + Sealed {
     /// Get the `Max-Age`, if set.
     fn max_age(&self) -> Option<Duration>;
 
+    /// Get the `Expires`, if set.
+    ///
+    /// This is an absolute point in time, as opposed to `max_age`.
+    fn expires(&self) -> Option<SystemTime>;
+
     /// Get if the `HttpOnly` attribute was on this cookie.
     fn http_only(&self) -> bool;
 
@@ -52,6 +110,64 @@ pub trait Cookie: fmt::Debug + fmt::Display + Sealed {
 
     /// Get the `SameSite`, if set.
     fn same_site(&self) -> Option<SameSite>;
+
+    /// Get the `Priority`, if set.
+    fn priority(&self) -> Option<Priority>;
+
+    /// Get if the `Partitioned` attribute was on this cookie.
+    fn partitioned(&self) -> bool;
+
+    /// Display this cookie as a `Set-Cookie` string with some
+    /// `SerializeOptions`.
+    fn display_with<'a>(&'a self, opts: &'a SerializeOptions) -> DisplayWith<'a>
+    where
+        Self: Sized,
+    {
+        DisplayWith::new(self, opts)
+    }
+
+    /// Get the value of this cookie, decoded with a `Codec`.
+    ///
+    /// # Error
+    ///
+    /// Returns an error if the value isn't valid for the codec.
+    fn decoded_value(&self, codec: &dyn Codec) -> Result<Cow<'_, [u8]>, Error> {
+        codec.decode(self.value())
+    }
+
+    /// Get the value of this cookie, decoded with a `Codec` into a string.
+    ///
+    /// # Error
+    ///
+    /// Returns an error if the value isn't valid for the codec, or if the
+    /// decoded bytes aren't valid UTF-8.
+    fn decoded_str(&self, codec: &dyn Codec) -> Result<Cow<'_, str>, Error> {
+        match self.decoded_value(codec)? {
+            Cow::Borrowed(bytes) => std::str::from_utf8(bytes)
+                .map(Cow::Borrowed)
+                .map_err(|_| Error::invalid_value()),
+            Cow::Owned(bytes) => String::from_utf8(bytes)
+                .map(Cow::Owned)
+                .map_err(|_| Error::invalid_value()),
+        }
+    }
+
+    /// Get the value of this cookie, deserialized into a `T`.
+    ///
+    /// This reads values set with `Builder::typed_value`.
+    ///
+    /// # Error
+    ///
+    /// Returns an error if the value wasn't encoded by
+    /// `Builder::typed_value`, or doesn't deserialize into a `T`.
+    #[cfg(feature = "serde")]
+    fn typed_value<T: serde::de::DeserializeOwned>(&self) -> Result<T, Error>
+    where
+        Self: Sized,
+    {
+        let json = self.decoded_value(&codec::Base64Url)?;
+        serde_json::from_slice(&json).map_err(|_| Error::invalid_value())
+    }
 }
 
 mod sealed {
@@ -0,0 +1,305 @@
+//! Read and write the Netscape `cookies.txt` format.
+//!
+//! This is the format of the cookie files used by curl, wget and many
+//! browser extensions. Each line is a cookie, with the tab-separated fields
+//! domain, include-subdomains, path, secure, expiry, name and value. A
+//! domain prefixed with `#HttpOnly_` is an `HttpOnly` cookie, other lines
+//! starting with `#` are comments.
+//!
+//! Requires the `jar` feature.
+//!
+//! # Example
+//!
+//! ```
+//! use cookies::{netscape, Cookie, CookieJar};
+//!
+//! let file = "\
+//! ## Netscape HTTP Cookie File
+//! .hyper.rs\tTRUE\t/\tTRUE\t0\tsession\tabc
+//! #HttpOnly_www.hyper.rs\tFALSE\t/guides\tFALSE\t0\tlang\ten
+//! bad line
+//! ";
+//!
+//! let mut jar = CookieJar::new();
+//! for cookie in netscape::parse(file) {
+//!     match cookie {
+//!         Ok(cookie) => jar.insert(cookie).unwrap(),
+//!         Err(err) => assert_eq!(err.line(), 4),
+//!     }
+//! }
+//!
+//! let lang = jar.get("www.hyper.rs", "/guides", "lang").unwrap();
+//! assert!(lang.host_only());
+//! assert!(lang.http_only());
+//!
+//! let mut out = Vec::new();
+//! netscape::write(jar.iter(), &mut out).unwrap();
+//! ```
+
+use std::fmt;
+use std::io;
+
+use crate::parse::{self, Domain};
+use crate::util::{from_unix, to_unix};
+use crate::StoredCookie;
+
+const HTTP_ONLY_PREFIX: &str = "#HttpOnly_";
+
+/// An iterator over the cookies of a `cookies.txt` file, created by
+/// `parse`.
+#[derive(Debug)]
+pub struct Parse<'a> {
+    lines: std::iter::Enumerate<std::str::Lines<'a>>,
+}
+
+/// An error for a malformed line of a `cookies.txt` file.
+#[derive(Debug)]
+pub struct LineError {
+    line: usize,
+    kind: Kind,
+}
+
+#[derive(Debug)]
+enum Kind {
+    Fields,
+    Flag,
+    Expiry,
+    Cookie(crate::Error),
+}
+
+/// Parse the cookies of a `cookies.txt` file.
+///
+/// Each cookie line yields a `StoredCookie`, to be inserted into a
+/// `CookieJar`, or an error for a malformed line. Blank lines and comments
+/// are skipped.
+pub fn parse(src: &str) -> Parse<'_> {
+    Parse {
+        lines: src.lines().enumerate(),
+    }
+}
+
+/// Write cookies in the `cookies.txt` format, with its usual header.
+///
+/// Partitioned cookies can't be written in this format, and are skipped.
+///
+/// # Error
+///
+/// Returns any error from writing into `dst`.
+pub fn write<'a, W>(
+    cookies: impl IntoIterator<Item = &'a StoredCookie>,
+    dst: &mut W,
+) -> io::Result<()>
+where
+    W: io::Write + ?Sized,
+{
+    dst.write_all(b"# Netscape HTTP Cookie File\n")?;
+
+    for c in cookies {
+        if c.partition_key.is_some() {
+            continue;
+        }
+
+        let expiry = c.expiry.map_or(0, |expiry| to_unix(expiry).max(1));
+        writeln!(
+            dst,
+            "{}{}{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            if c.http_only { HTTP_ONLY_PREFIX } else { "" },
+            if c.host_only { "" } else { "." },
+            c.domain,
+            flag(!c.host_only),
+            c.path,
+            flag(c.secure),
+            expiry,
+            c.name,
+            c.value,
+        )?;
+    }
+
+    Ok(())
+}
+
+fn flag(enabled: bool) -> &'static str {
+    if enabled {
+        "TRUE"
+    } else {
+        "FALSE"
+    }
+}
+
+fn parse_flag(s: &str) -> Result<bool, Kind> {
+    if s.eq_ignore_ascii_case("true") {
+        Ok(true)
+    } else if s.eq_ignore_ascii_case("false") {
+        Ok(false)
+    } else {
+        Err(Kind::Flag)
+    }
+}
+
+fn parse_line(line: &str) -> Result<StoredCookie, Kind> {
+    let (line, http_only) = match line.strip_prefix(HTTP_ONLY_PREFIX) {
+        Some(rest) => (rest, true),
+        None => (line, false),
+    };
+
+    let fields = line.split('\t').collect::<Vec<_>>();
+    // Some writers leave out the tab before an empty value.
+    let (domain, subdomains, path, secure, expiry, name, value) = match fields[..] {
+        [d, s, p, sec, e, n, v] => (d, s, p, sec, e, n, v),
+        [d, s, p, sec, e, n] => (d, s, p, sec, e, n, ""),
+        _ => return Err(Kind::Fields),
+    };
+
+    let host_only = !parse_flag(subdomains)?;
+    let secure = parse_flag(secure)?;
+    let expiry = match expiry.parse::<i64>() {
+        Ok(0) => None,
+        Ok(secs) if secs > 0 => Some(from_unix(secs)),
+        _ => return Err(Kind::Expiry),
+    };
+
+    parse::validate_name(name).map_err(Kind::Cookie)?;
+    parse::validate_value(value).map_err(Kind::Cookie)?;
+    if !parse::is_valid_path(path) {
+        return Err(Kind::Cookie(crate::Error::invalid_path()));
+    }
+    let domain = match parse::validate_domain(domain) {
+        Domain::AsIs => domain,
+        Domain::LeadingDot => &domain[1..],
+        Domain::Invalid => return Err(Kind::Cookie(crate::Error::invalid_domain())),
+    };
+
+    let mut cookie = StoredCookie::new(
+        name.to_owned(),
+        value.to_owned(),
+        domain.to_ascii_lowercase(),
+        path.to_owned(),
+    );
+    cookie.expiry = expiry;
+    cookie.host_only = host_only;
+    cookie.secure = secure;
+    cookie.http_only = http_only;
+    Ok(cookie)
+}
+
+// ===== impl Parse =====
+
+impl<'a> Iterator for Parse<'a> {
+    type Item = Result<StoredCookie, LineError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (i, line) in &mut self.lines {
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty()
+                || (line.starts_with('#') && !line.starts_with(HTTP_ONLY_PREFIX))
+            {
+                continue;
+            }
+
+            return Some(parse_line(line).map_err(|kind| LineError { line: i + 1, kind }));
+        }
+        None
+    }
+}
+
+// ===== impl LineError =====
+
+impl LineError {
+    /// Get the number of the malformed line, starting at 1.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match self.kind {
+            Kind::Fields => f.write_str("expected 7 tab-separated fields"),
+            Kind::Flag => f.write_str("expected TRUE or FALSE"),
+            Kind::Expiry => f.write_str("invalid expiry"),
+            Kind::Cookie(ref err) => fmt::Display::fmt(err, f),
+        }
+    }
+}
+
+impl std::error::Error for LineError {}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use super::*;
+    use crate::Cookie;
+
+    #[test]
+    fn parse_lines() {
+        let src = "# Netscape HTTP Cookie File\r\n\
+                   \r\n\
+                   .hyper.rs\tTRUE\t/\tTRUE\t1558473131\ta\t1\r\n\
+                   #HttpOnly_hyper.rs\tFALSE\t/\tFALSE\t0\tb\t\r\n\
+                   hyper.rs\tFALSE\t/\tFALSE\t0\tc\r\n";
+
+        let cookies = parse(src).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(cookies.len(), 3);
+
+        assert_eq!(cookies[0].host(), "hyper.rs");
+        assert!(!cookies[0].host_only());
+        assert!(cookies[0].secure());
+        assert_eq!(cookies[0].expires(), Some(from_unix(1_558_473_131)));
+
+        assert!(cookies[1].host_only());
+        assert!(cookies[1].http_only());
+        assert!(!cookies[1].is_persistent());
+        assert_eq!(cookies[1].value(), "");
+
+        assert_eq!(cookies[2].name(), "c");
+    }
+
+    #[test]
+    fn malformed_lines() {
+        let src = "hyper.rs\tFALSE\t/\n\
+                   hyper.rs\tMAYBE\t/\tFALSE\t0\ta\t1\n\
+                   hyper.rs\tFALSE\t/\tFALSE\tsoon\ta\t1\n\
+                   hyper.rs\tFALSE\t/\tFALSE\t0\ta b\t1\n\
+                   hyper.rs\tFALSE\t/\tFALSE\t0\tok\t1\n";
+
+        let results = parse(src).collect::<Vec<_>>();
+        let errors = results
+            .iter()
+            .filter_map(|r| r.as_ref().err())
+            .map(|err| err.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            [
+                "line 1: expected 7 tab-separated fields",
+                "line 2: expected TRUE or FALSE",
+                "line 3: invalid expiry",
+                "line 4: cookie name contains invalid character",
+            ]
+        );
+        assert_eq!(results[4].as_ref().unwrap().name(), "ok");
+    }
+
+    #[test]
+    fn roundtrip() {
+        let src = "# Netscape HTTP Cookie File\n\
+                   .hyper.rs\tTRUE\t/\tTRUE\t1558473131\ta\t1\n\
+                   #HttpOnly_www.hyper.rs\tFALSE\t/guides\tFALSE\t0\tb\t2\n";
+
+        let cookies = parse(src).collect::<Result<Vec<_>, _>>().unwrap();
+        let mut out = Vec::new();
+        write(&cookies, &mut out).unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap(), src);
+    }
+
+    #[test]
+    fn expired_is_kept() {
+        let src = "hyper.rs\tFALSE\t/\tFALSE\t1\ta\t1\n";
+        let cookie = parse(src).next().unwrap().unwrap();
+        assert!(cookie.expires().unwrap() < SystemTime::now());
+        assert_eq!(cookie.expires(), Some(UNIX_EPOCH + Duration::from_secs(1)));
+    }
+}
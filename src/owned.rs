@@ -0,0 +1,337 @@
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use super::{Cookie, Priority, SameSite, Sealed};
+use crate::util;
+
+/// A `Cookie` that owns all of its attributes.
+///
+/// Any `Cookie` can be converted into an `OwnedCookie`, which is useful to
+/// store a cookie that borrows its source, such as a parsed one.
+///
+/// # Example
+///
+/// ```
+/// use cookies::{Cookie, OwnedCookie};
+///
+/// let owned = {
+///     let raw = String::from("foo=bar; Path=/");
+///     let parsed = cookies::parse(&raw).unwrap();
+///     OwnedCookie::from(&parsed)
+/// };
+///
+/// assert_eq!(owned.name(), "foo");
+/// assert_eq!(owned.path(), Some("/"));
+/// ```
+#[derive(Clone)]
+pub struct OwnedCookie {
+    name: String,
+    value: String,
+    domain: Option<String>,
+    path: Option<String>,
+    max_age: Option<Duration>,
+    expires: Option<SystemTime>,
+    secure: bool,
+    http_only: bool,
+    same_site: Option<SameSite>,
+    priority: Option<Priority>,
+    partitioned: bool,
+}
+
+// ===== impl OwnedCookie =====
+
+impl<'a, C: Cookie + ?Sized> From<&'a C> for OwnedCookie {
+    fn from(cookie: &'a C) -> OwnedCookie {
+        OwnedCookie {
+            name: cookie.name().to_owned(),
+            value: cookie.value().to_owned(),
+            domain: cookie.domain().map(ToOwned::to_owned),
+            path: cookie.path().map(ToOwned::to_owned),
+            max_age: cookie.max_age(),
+            expires: cookie.expires(),
+            secure: cookie.secure(),
+            http_only: cookie.http_only(),
+            same_site: cookie.same_site(),
+            priority: cookie.priority(),
+            partitioned: cookie.partitioned(),
+        }
+    }
+}
+
+impl Cookie for OwnedCookie {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn value(&self) -> &str {
+        &self.value
+    }
+
+    fn domain(&self) -> Option<&str> {
+        self.domain.as_deref()
+    }
+
+    fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    fn max_age(&self) -> Option<Duration> {
+        self.max_age
+    }
+
+    fn expires(&self) -> Option<SystemTime> {
+        self.expires
+    }
+
+    fn http_only(&self) -> bool {
+        self.http_only
+    }
+
+    fn secure(&self) -> bool {
+        self.secure
+    }
+
+    fn same_site(&self) -> Option<SameSite> {
+        self.same_site
+    }
+
+    fn priority(&self) -> Option<Priority> {
+        self.priority
+    }
+
+    fn partitioned(&self) -> bool {
+        self.partitioned
+    }
+}
+
+impl Sealed for OwnedCookie {}
+
+impl fmt::Debug for OwnedCookie {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        util::debug(self, f)
+    }
+}
+
+impl fmt::Display for OwnedCookie {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        util::display(self, f)
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use std::borrow::Cow;
+    use std::time::SystemTime;
+
+    use serde::de::{self, Deserialize, Deserializer};
+    use serde::ser::{Serialize, Serializer};
+
+    use super::OwnedCookie;
+    use crate::util::{from_unix, saturating_add, to_unix};
+    use crate::{Cookie, Error, Priority, SameSite};
+
+    /// The structured form of a cookie.
+    ///
+    /// The expiry is always absolute, in seconds since the Unix epoch, so
+    /// that it means the same thing whenever it is deserialized.
+    #[derive(serde::Serialize, serde::Deserialize)]
+    struct Repr<'a> {
+        #[serde(borrow)]
+        name: Cow<'a, str>,
+        #[serde(borrow)]
+        value: Cow<'a, str>,
+        #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+        domain: Option<Cow<'a, str>>,
+        #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+        path: Option<Cow<'a, str>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expires: Option<i64>,
+        #[serde(default)]
+        secure: bool,
+        #[serde(default)]
+        http_only: bool,
+        #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+        same_site: Option<Cow<'a, str>>,
+        #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+        priority: Option<Cow<'a, str>>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        partitioned: bool,
+    }
+
+    impl Serialize for dyn Cookie + '_ {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let expires = self
+                .expires()
+                .or_else(|| {
+                    self.max_age()
+                        .map(|ma| saturating_add(SystemTime::now(), ma))
+                })
+                .map(to_unix);
+
+            Repr {
+                name: Cow::Borrowed(self.name()),
+                value: Cow::Borrowed(self.value()),
+                domain: self.domain().map(Cow::Borrowed),
+                path: self.path().map(Cow::Borrowed),
+                expires,
+                secure: self.secure(),
+                http_only: self.http_only(),
+                same_site: self.same_site().map(|ss| Cow::Borrowed(ss.as_str())),
+                priority: self.priority().map(|p| Cow::Borrowed(p.as_str())),
+                partitioned: self.partitioned(),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl Serialize for OwnedCookie {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            (self as &dyn Cookie).serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for OwnedCookie {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let repr = Repr::deserialize(deserializer)?;
+
+            crate::parse::validate_name(&repr.name).map_err(de::Error::custom)?;
+            crate::parse::validate_value(&repr.value).map_err(de::Error::custom)?;
+
+            if let Some(ref path) = repr.path {
+                if !crate::parse::is_valid_path(path) {
+                    return Err(de::Error::custom(Error::invalid_path()));
+                }
+            }
+
+            if let Some(ref domain) = repr.domain {
+                use crate::parse::Domain;
+                match crate::parse::validate_domain(domain) {
+                    Domain::AsIs => (),
+                    Domain::LeadingDot | Domain::Invalid => {
+                        return Err(de::Error::custom(Error::invalid_domain()));
+                    }
+                }
+            }
+
+            let same_site = repr
+                .same_site
+                .as_deref()
+                .map(|ss| {
+                    ss.parse::<SameSite>()
+                        .map_err(|_| de::Error::unknown_variant(ss, SameSite::VARIANTS))
+                })
+                .transpose()?;
+
+            let priority = repr
+                .priority
+                .as_deref()
+                .map(|p| {
+                    p.parse::<Priority>()
+                        .map_err(|_| de::Error::unknown_variant(p, Priority::VARIANTS))
+                })
+                .transpose()?;
+
+            Ok(OwnedCookie {
+                name: repr.name.into_owned(),
+                value: repr.value.into_owned(),
+                domain: repr.domain.map(Cow::into_owned),
+                path: repr.path.map(Cow::into_owned),
+                max_age: None,
+                expires: repr.expires.map(from_unix),
+                secure: repr.secure,
+                http_only: repr.http_only,
+                same_site,
+                priority,
+                partitioned: repr.partitioned,
+            })
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        use super::*;
+        use crate::Builder;
+
+        #[test]
+        fn roundtrip() {
+            let at = UNIX_EPOCH + Duration::from_secs(1_558_473_131);
+            let c = Builder::new("foo", "bar")
+                .path("/")
+                .domain("hyper.rs")
+                .expires(at)
+                .secure(true)
+                .same_site(SameSite::LAX)
+                .build()
+                .unwrap();
+
+            let json = serde_json::to_string(&c as &dyn Cookie).unwrap();
+            assert_eq!(
+                json,
+                r#"{"name":"foo","value":"bar","domain":"hyper.rs","path":"/","expires":1558473131,"secure":true,"http_only":false,"same_site":"Lax"}"#
+            );
+
+            let owned: OwnedCookie = serde_json::from_str(&json).unwrap();
+            assert_eq!(owned.to_string(), c.to_string());
+            assert_eq!(owned.expires(), Some(at));
+        }
+
+        #[test]
+        fn max_age_becomes_absolute() {
+            let c = Builder::new("foo", "bar")
+                .max_age(Duration::from_secs(60))
+                .build()
+                .unwrap();
+            let before = SystemTime::now();
+
+            let json = serde_json::to_string(&c as &dyn Cookie).unwrap();
+            let owned: OwnedCookie = serde_json::from_str(&json).unwrap();
+
+            assert_eq!(owned.max_age(), None);
+            let expires = owned.expires().expect("absolute expires");
+            assert!(expires + Duration::from_secs(1) >= before + Duration::from_secs(60));
+        }
+
+        #[test]
+        fn huge_times_saturate() {
+            let c = Builder::new("foo", "bar")
+                .max_age(Duration::from_secs(u64::MAX))
+                .build()
+                .unwrap();
+            let json = serde_json::to_string(&c as &dyn Cookie).unwrap();
+            assert!(json.contains(r#""expires":253402300799"#), "{}", json);
+
+            for expires in &[i64::MAX, i64::MIN] {
+                let json = format!(r#"{{"name":"foo","value":"bar","expires":{}}}"#, expires);
+                let owned: OwnedCookie = serde_json::from_str(&json).unwrap();
+                assert!(owned.expires().is_some());
+            }
+        }
+
+        #[test]
+        fn validates() {
+            serde_json::from_str::<OwnedCookie>(r#"{"name":"f o o","value":"bar"}"#)
+                .expect_err("invalid name");
+            serde_json::from_str::<OwnedCookie>(r#"{"name":"foo","value":"bar","path":"x"}"#)
+                .expect_err("invalid path");
+            serde_json::from_str::<OwnedCookie>(
+                r#"{"name":"foo","value":"bar","same_site":"Sometimes"}"#,
+            )
+            .expect_err("unknown same_site");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_parsed() {
+        let orig = "foo=bar; Path=/; Domain=hyper.rs; HttpOnly; Secure; SameSite=Strict";
+        let owned = OwnedCookie::from(&crate::parse(orig).unwrap());
+
+        assert_eq!(owned.to_string(), orig);
+    }
+}
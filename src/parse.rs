@@ -1,10 +1,10 @@
 use std::fmt;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
 use super::{Cookie, Error, Sealed};
-use crate::util::{self, SameSite};
+use crate::util::{self, Priority, SameSite};
 
-const MAX_LENGTH: usize = 4096;
+pub(crate) const MAX_LENGTH: usize = 4096;
 
 // Not:
 // - PartialEq: determining equality depends on what you need equality for.
@@ -17,9 +17,12 @@ struct Parsed<T> {
     domain: Option<Indexed>,
     path: Option<Indexed>,
     max_age: Option<Duration>,
+    expires: Option<SystemTime>,
     secure: bool,
     http_only: bool,
     same_site: Option<SameSite>,
+    priority: Option<Priority>,
+    partitioned: bool,
 }
 
 // Cookie max length is 4kb, u16 can fit 64kb
@@ -60,6 +63,10 @@ impl<T: AsRef<str>> Cookie for Parsed<T> {
         self.max_age
     }
 
+    fn expires(&self) -> Option<SystemTime> {
+        self.expires
+    }
+
     fn http_only(&self) -> bool {
         self.http_only
     }
@@ -71,6 +78,14 @@ impl<T: AsRef<str>> Cookie for Parsed<T> {
     fn same_site(&self) -> Option<SameSite> {
         self.same_site
     }
+
+    fn priority(&self) -> Option<Priority> {
+        self.priority
+    }
+
+    fn partitioned(&self) -> bool {
+        self.partitioned
+    }
 }
 
 impl<T: AsRef<str>> Sealed for Parsed<T> {}
@@ -118,9 +133,12 @@ pub fn parse<T: AsRef<str>>(src: T) -> Result<impl Cookie, Error> {
         domain: None,
         path: None,
         max_age: None,
+        expires: None,
         http_only: false,
         secure: false,
         same_site: None,
+        priority: None,
+        partitioned: false,
     };
 
     let s = cookie.src.as_ref();
@@ -159,6 +177,8 @@ pub fn parse<T: AsRef<str>>(src: T) -> Result<impl Cookie, Error> {
             cookie.secure = true;
         } else if name.eq_ignore_ascii_case("httponly") {
             cookie.http_only = true;
+        } else if name.eq_ignore_ascii_case("partitioned") {
+            cookie.partitioned = true;
         } else if let Some(value) = value {
             if name.eq_ignore_ascii_case("max-age") {
                 cookie.max_age = match value.parse::<i64>() {
@@ -186,13 +206,15 @@ pub fn parse<T: AsRef<str>>(src: T) -> Result<impl Cookie, Error> {
             } else if name.eq_ignore_ascii_case("expires") {
                 expires = Some(value);
             } else if name.eq_ignore_ascii_case("samesite") {
-                cookie.same_site = if value.eq_ignore_ascii_case("lax") {
-                    Some(SameSite::LAX)
-                } else if value.eq_ignore_ascii_case("strict") {
-                    Some(SameSite::STRICT)
-                } else {
+                cookie.same_site = match value.parse() {
+                    Ok(same_site) => Some(same_site),
                     // unknown SameSite, skip as mandated by spec
-                    continue;
+                    Err(_) => continue,
+                }
+            } else if name.eq_ignore_ascii_case("priority") {
+                cookie.priority = match value.parse() {
+                    Ok(priority) => Some(priority),
+                    Err(_) => continue,
                 }
             } else {
                 // ignoring unknown attribute, as mandated by RFC6265
@@ -207,6 +229,7 @@ pub fn parse<T: AsRef<str>>(src: T) -> Result<impl Cookie, Error> {
 
         if let Ok(tm) = tm {
             let expires_tspec = tm.to_timespec();
+            cookie.expires = Some(util::from_unix(expires_tspec.sec));
             let now = time::get_time();
             if expires_tspec.sec > now.sec && expires_tspec.sec > 0 {
                 // as u64: Just checked the values are positive
@@ -222,6 +245,52 @@ pub fn parse<T: AsRef<str>>(src: T) -> Result<impl Cookie, Error> {
     Ok(cookie)
 }
 
+/// Parse the name/value pairs of a `Cookie` request header.
+///
+/// Pairs with an invalid name are skipped.
+///
+/// # Example
+///
+/// ```
+/// let mut pairs = cookies::parse_pairs("foo=bar; baz=qux");
+///
+/// assert_eq!(pairs.next(), Some(("foo", "bar")));
+/// assert_eq!(pairs.next(), Some(("baz", "qux")));
+/// assert_eq!(pairs.next(), None);
+/// ```
+pub fn parse_pairs(src: &str) -> impl Iterator<Item = (&str, &str)> {
+    src.split(';').filter_map(|pair| {
+        let i = pair.find('=')?;
+        let name = pair[..i].trim();
+        validate_name(name).ok()?;
+        Some((name, pair[(i + 1)..].trim()))
+    })
+}
+
+/// Parse some string as a `Cookie` received in a response to a request for
+/// `url`.
+///
+/// In addition to `parse`, the `Domain` of the cookie is checked against the
+/// URL: it must domain-match the host, and must not be a public suffix such
+/// as `com` or `co.uk` (see `PublicSuffixList`).
+///
+/// Requires the `jar` feature.
+///
+/// # Example
+///
+/// ```
+/// let url = url::Url::parse("https://www.example.co.uk/").unwrap();
+///
+/// cookies::parse_for_url("foo=bar; Domain=example.co.uk", &url).expect("valid domain");
+/// cookies::parse_for_url("foo=bar; Domain=co.uk", &url).expect_err("public suffix");
+/// ```
+#[cfg(feature = "jar")]
+pub fn parse_for_url<T: AsRef<str>>(src: T, url: &url::Url) -> Result<impl Cookie, Error> {
+    let cookie = parse(src)?;
+    crate::jar::resolve_domain(url, cookie.domain(), crate::PublicSuffixList::embedded())?;
+    Ok(cookie)
+}
+
 pub(crate) fn validate_name(n: &str) -> Result<(), Error> {
     if n.is_empty() {
         return Err(Error::invalid_name());
@@ -361,6 +430,20 @@ mod tests {
         assert_eq!(c.max_age(), Some(secs_3), "last 'valid' Max-Age");
     }
 
+    #[test]
+    fn expires() {
+        use std::time::UNIX_EPOCH;
+
+        let c = parse("foo=bar; Expires=Tue, 21 May 2019 21:12:11 GMT").unwrap();
+        let at = UNIX_EPOCH + Duration::from_secs(1_558_473_131);
+        assert_eq!(c.expires(), Some(at), "keeps absolute Expires");
+        assert_eq!(c.max_age(), Some(Duration::from_secs(0)), "already expired");
+        assert_eq!(
+            c.to_string(),
+            "foo=bar; Max-Age=0; Expires=Tue, 21 May 2019 21:12:11 GMT"
+        );
+    }
+
     #[test]
     fn path() {
         let c = parse("foo=bar; Path=/").unwrap();
@@ -412,6 +495,35 @@ mod tests {
         assert!(c.http_only());
     }
 
+    #[test]
+    fn priority() {
+        let c = parse("foo=bar; Priority=HIGH").unwrap();
+        assert_eq!(c.priority(), Some(Priority::HIGH));
+
+        let c = parse("foo=bar; Priority=low").unwrap();
+        assert_eq!(c.priority(), Some(Priority::LOW));
+
+        let c = parse("foo=bar; Priority=urgent").unwrap();
+        assert_eq!(c.priority(), None);
+    }
+
+    #[test]
+    fn partitioned() {
+        let c = parse("foo=bar; Secure; Partitioned").unwrap();
+        assert!(c.partitioned());
+        assert_eq!(c.to_string(), "foo=bar; Secure; Partitioned");
+
+        let c = parse("foo=bar; Secure").unwrap();
+        assert!(!c.partitioned());
+    }
+
+    #[test]
+    fn samesite_none() {
+        let c = parse("foo=bar; SameSite=None; Secure").unwrap();
+        assert_eq!(c.same_site(), Some(SameSite::NONE));
+        assert_eq!(c.to_string(), "foo=bar; Secure; SameSite=None");
+    }
+
     #[test]
     fn samesite_bogus_value() {
         // SameSite spec says we should ignore the attribute completely
@@ -419,6 +531,12 @@ mod tests {
         assert_eq!(c.same_site(), None);
     }
 
+    #[test]
+    fn pairs() {
+        let pairs = parse_pairs(" foo=bar;baz= qux ; empty=; wat; a b=c").collect::<Vec<_>>();
+        assert_eq!(pairs, vec![("foo", "bar"), ("baz", "qux"), ("empty", "")]);
+    }
+
     #[test]
     fn parsed_to_boxed() {
         let c = parse("foo=bar").unwrap();
@@ -0,0 +1,235 @@
+use std::time::Duration;
+
+use super::{Builder, Cookie, Error, SameSite};
+use crate::util;
+
+/// Application-wide defaults for the cookies an application emits.
+///
+/// A `Policy` fills in any attribute a cookie didn't set itself. Attributes
+/// set on a `Builder` after it was seeded by a policy always win.
+///
+/// # Example
+///
+/// ```
+/// use cookies::{Cookie, Policy, SameSite};
+///
+/// let policy = Policy::new()
+///     .path("/")
+///     .secure(true)
+///     .http_only(true)
+///     .same_site(SameSite::LAX);
+///
+/// let cookie = policy
+///     .builder("session", "abc123")
+///     .build()
+///     .expect("valid cookie");
+///
+/// assert_eq!(cookie.path(), Some("/"));
+/// assert!(cookie.secure());
+/// assert!(cookie.http_only());
+/// assert_eq!(cookie.same_site(), Some(SameSite::LAX));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Policy {
+    domain: Option<String>,
+    path: Option<String>,
+    max_age: Option<Duration>,
+    secure: bool,
+    http_only: bool,
+    same_site: Option<SameSite>,
+}
+
+struct WithDefaults<'p, C>(C, &'p Policy);
+
+// ===== impl Policy =====
+
+impl Policy {
+    /// Create a `Policy` that doesn't fill in any attributes.
+    pub fn new() -> Policy {
+        Policy::default()
+    }
+
+    /// Set the default `Domain` attribute.
+    pub fn domain(mut self, domain: impl Into<String>) -> Policy {
+        self.domain = Some(domain.into());
+        self
+    }
+
+    /// Set the default `Path` attribute.
+    pub fn path(mut self, path: impl Into<String>) -> Policy {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Set the default `Max-Age` attribute.
+    pub fn max_age(mut self, max_age: Duration) -> Policy {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Enable or disable the `Secure` attribute by default.
+    pub fn secure(mut self, secure: bool) -> Policy {
+        self.secure = secure;
+        self
+    }
+
+    /// Enable or disable the `HttpOnly` attribute by default.
+    pub fn http_only(mut self, http_only: bool) -> Policy {
+        self.http_only = http_only;
+        self
+    }
+
+    /// Set the default `SameSite` attribute.
+    pub fn same_site(mut self, same_site: impl Into<Option<SameSite>>) -> Policy {
+        self.same_site = same_site.into();
+        self
+    }
+
+    /// Start a new `Builder` seeded with the defaults of this policy.
+    ///
+    /// Any attribute set on the returned builder overrides the policy.
+    pub fn builder<'p, N, V>(&'p self, name: N, value: V) -> Builder<impl Cookie + 'p>
+    where
+        N: AsRef<str> + 'p,
+        V: AsRef<str> + 'p,
+    {
+        self.apply(Builder::new(name, value))
+    }
+
+    /// Wrap an existing `Cookie`, filling in the attributes it is missing.
+    ///
+    /// Since a flag that is off can't be told apart from one that was never
+    /// set, `Secure` and `HttpOnly` are enabled if either the cookie or the
+    /// policy enables them.
+    pub fn wrap<'p, C: Cookie + 'p>(&'p self, cookie: C) -> Builder<impl Cookie + 'p> {
+        self.apply(Builder::wrap(cookie))
+    }
+
+    // private
+
+    fn apply<'p, C: Cookie + 'p>(&'p self, builder: Builder<C>) -> Builder<impl Cookie + 'p> {
+        builder.and_then(move |c| {
+            self.validate()?;
+            Ok(util::Delegated(WithDefaults(c, self)))
+        })
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        if let Some(ref path) = self.path {
+            if !crate::parse::is_valid_path(path) {
+                return Err(Error::invalid_path());
+            }
+        }
+
+        if let Some(ref domain) = self.domain {
+            use crate::parse::Domain;
+            match crate::parse::validate_domain(domain) {
+                Domain::AsIs => (),
+                Domain::LeadingDot | Domain::Invalid => return Err(Error::invalid_domain()),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// ===== impl WithDefaults =====
+
+impl<'p, C: Cookie> util::Delegate for WithDefaults<'p, C> {
+    type Cookie = C;
+    fn cookie(&self) -> &Self::Cookie {
+        &self.0
+    }
+
+    fn domain(&self) -> Option<&str> {
+        self.0.domain().or(self.1.domain.as_deref())
+    }
+
+    fn path(&self) -> Option<&str> {
+        self.0.path().or(self.1.path.as_deref())
+    }
+
+    fn max_age(&self) -> Option<Duration> {
+        self.0.max_age().or(self.1.max_age)
+    }
+
+    fn http_only(&self) -> bool {
+        self.0.http_only() || self.1.http_only
+    }
+
+    fn secure(&self) -> bool {
+        self.0.secure() || self.1.secure
+    }
+
+    fn same_site(&self) -> Option<SameSite> {
+        self.0.same_site().or(self.1.same_site)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> Policy {
+        Policy::new()
+            .path("/")
+            .secure(true)
+            .http_only(true)
+            .same_site(SameSite::LAX)
+    }
+
+    #[test]
+    fn builder_fills_defaults() {
+        let policy = policy();
+        let c = policy.builder("foo", "bar").build().unwrap();
+
+        assert_eq!(
+            c.to_string(),
+            "foo=bar; Path=/; HttpOnly; Secure; SameSite=Lax"
+        );
+    }
+
+    #[test]
+    fn builder_overrides_defaults() {
+        let policy = policy();
+        let c = policy
+            .builder("foo", "bar")
+            .path("/admin")
+            .secure(false)
+            .same_site(SameSite::STRICT)
+            .build()
+            .unwrap();
+
+        assert_eq!(c.path(), Some("/admin"));
+        assert!(!c.secure());
+        assert!(c.http_only());
+        assert_eq!(c.same_site(), Some(SameSite::STRICT));
+    }
+
+    #[test]
+    fn wrap_keeps_existing_attributes() {
+        let policy = policy().domain("hyper.rs");
+        let parsed = crate::parse("foo=bar; Path=/docs; SameSite=Strict").unwrap();
+        let c = policy.wrap(parsed).build().unwrap();
+
+        assert_eq!(c.path(), Some("/docs"));
+        assert_eq!(c.domain(), Some("hyper.rs"));
+        assert!(c.secure());
+        assert_eq!(c.same_site(), Some(SameSite::STRICT));
+    }
+
+    #[test]
+    fn invalid_defaults() {
+        Policy::new()
+            .path("no-slash")
+            .builder("foo", "bar")
+            .build()
+            .expect_err("invalid default path");
+
+        Policy::new()
+            .domain(".hyper.rs")
+            .builder("foo", "bar")
+            .build()
+            .expect_err("default domain with leading dot");
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// A snapshot of the Public Suffix List, embedded at build time.
+static EMBEDDED_SRC: &str = include_str!("../data/public_suffix_list.dat");
+
+/// The Public Suffix List, of domains under which anyone can register names.
+///
+/// A cookie jar uses it to reject cookies for a `Domain` such as `com` or
+/// `co.uk`, which would otherwise be sent to every site under it.
+///
+/// Domains passed to its methods should be lowercase ASCII, with any
+/// internationalized labels in punycode, such as the host of a `url::Url`.
+///
+/// # Example
+///
+/// ```
+/// use cookies::PublicSuffixList;
+///
+/// let list = PublicSuffixList::embedded();
+///
+/// assert!(list.is_public_suffix("co.uk"));
+/// assert_eq!(list.registrable_domain("www.example.co.uk"), Some("example.co.uk"));
+/// ```
+pub struct PublicSuffixList {
+    rules: HashSet<String>,
+    wildcards: HashSet<String>,
+    exceptions: HashSet<String>,
+}
+
+// ===== impl PublicSuffixList =====
+
+impl PublicSuffixList {
+    /// Get the snapshot of the list embedded in this crate.
+    pub fn embedded() -> &'static PublicSuffixList {
+        static EMBEDDED: OnceLock<PublicSuffixList> = OnceLock::new();
+        EMBEDDED.get_or_init(|| PublicSuffixList::parse(EMBEDDED_SRC))
+    }
+
+    /// Parse a list in the format of `public_suffix_list.dat`.
+    ///
+    /// Comments, blank lines and rules that aren't valid domains are
+    /// skipped.
+    pub fn parse(src: &str) -> PublicSuffixList {
+        let mut list = PublicSuffixList {
+            rules: HashSet::new(),
+            wildcards: HashSet::new(),
+            exceptions: HashSet::new(),
+        };
+
+        for line in src.lines() {
+            // Each rule is the first whitespace-delimited word on a line.
+            let rule = match line.split_whitespace().next() {
+                Some(rule) if !rule.starts_with("//") => rule,
+                _ => continue,
+            };
+
+            let (set, domain) = if let Some(domain) = rule.strip_prefix('!') {
+                (&mut list.exceptions, domain)
+            } else if let Some(domain) = rule.strip_prefix("*.") {
+                (&mut list.wildcards, domain)
+            } else {
+                (&mut list.rules, rule)
+            };
+
+            if let Ok(domain) = idna::domain_to_ascii(domain) {
+                set.insert(domain);
+            }
+        }
+
+        list
+    }
+
+    /// Load a newer list from a file in the format of
+    /// `public_suffix_list.dat`.
+    ///
+    /// # Error
+    ///
+    /// Returns an error if the file can't be read, or isn't UTF-8.
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<PublicSuffixList> {
+        let src = std::fs::read_to_string(path)?;
+        Ok(PublicSuffixList::parse(&src))
+    }
+
+    /// Get the public suffix of a domain.
+    ///
+    /// A domain not covered by any rule has its last label as the public
+    /// suffix.
+    pub fn public_suffix<'a>(&self, domain: &'a str) -> &'a str {
+        let domain = domain.trim_end_matches('.');
+
+        // Offsets of each suffix, from the whole domain to the last label.
+        let suffixes = std::iter::once(0)
+            .chain(domain.match_indices('.').map(|(i, _)| i + 1))
+            .collect::<Vec<_>>();
+
+        // An exception rule always wins, and its public suffix is the rule
+        // without its leftmost label.
+        for (n, &start) in suffixes.iter().enumerate() {
+            if self.exceptions.contains(&domain[start..]) {
+                return match suffixes.get(n + 1) {
+                    Some(&next) => &domain[next..],
+                    None => "",
+                };
+            }
+        }
+
+        // Otherwise, the longest matching rule wins.
+        for (n, &start) in suffixes.iter().enumerate() {
+            let suffix = &domain[start..];
+            if self.rules.contains(suffix) {
+                return suffix;
+            }
+            if let Some(&next) = suffixes.get(n + 1) {
+                if self.wildcards.contains(&domain[next..]) {
+                    return suffix;
+                }
+            }
+        }
+
+        // The default rule is `*`.
+        suffixes.last().map_or(domain, |&start| &domain[start..])
+    }
+
+    /// Check if a domain is a public suffix.
+    pub fn is_public_suffix(&self, domain: &str) -> bool {
+        let domain = domain.trim_end_matches('.');
+        !domain.is_empty() && self.public_suffix(domain) == domain
+    }
+
+    /// Get the registrable domain of a domain, which is its public suffix
+    /// and one more label.
+    ///
+    /// Returns `None` if the domain is itself a public suffix.
+    pub fn registrable_domain<'a>(&self, domain: &'a str) -> Option<&'a str> {
+        let domain = domain.trim_end_matches('.');
+        let suffix = self.public_suffix(domain);
+        if suffix.len() >= domain.len() {
+            return None;
+        }
+
+        let rest = &domain[..domain.len() - suffix.len() - 1];
+        let start = rest.rfind('.').map_or(0, |i| i + 1);
+        Some(&domain[start..])
+    }
+}
+
+impl fmt::Debug for PublicSuffixList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PublicSuffixList")
+            .field("rules", &self.rules.len())
+            .field("wildcards", &self.wildcards.len())
+            .field("exceptions", &self.exceptions.len())
+            .finish()
+    }
+}
+
+/// Check if a domain is a public suffix, using the embedded list.
+///
+/// See `PublicSuffixList::is_public_suffix`.
+pub fn is_public_suffix(domain: &str) -> bool {
+    PublicSuffixList::embedded().is_public_suffix(domain)
+}
+
+/// Get the registrable domain of a domain, using the embedded list.
+///
+/// See `PublicSuffixList::registrable_domain`.
+pub fn registrable_domain(domain: &str) -> Option<&str> {
+    PublicSuffixList::embedded().registrable_domain(domain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embedded() {
+        let list = PublicSuffixList::embedded();
+
+        assert!(list.is_public_suffix("com"));
+        assert!(list.is_public_suffix("co.uk"));
+        assert!(!list.is_public_suffix("hyper.rs"));
+        assert!(!list.is_public_suffix(""));
+
+        assert_eq!(list.registrable_domain("hyper.rs"), Some("hyper.rs"));
+        assert_eq!(list.registrable_domain("www.hyper.rs"), Some("hyper.rs"));
+        assert_eq!(
+            list.registrable_domain("a.b.example.co.uk"),
+            Some("example.co.uk")
+        );
+        assert_eq!(list.registrable_domain("co.uk"), None);
+
+        // punycode rules are matched
+        assert!(list.is_public_suffix("xn--55qx5d.cn"));
+    }
+
+    #[test]
+    fn rules() {
+        let list = PublicSuffixList::parse(
+            "// comment\n\
+             com\n\
+             *.ck  trailing words\n\
+             !www.ck\n\
+             \n\
+             uk\n\
+             co.uk\n",
+        );
+
+        assert_eq!(list.public_suffix("example.com"), "com");
+        assert_eq!(list.public_suffix("a.example.co.uk"), "co.uk");
+        assert_eq!(list.public_suffix("example.ck"), "example.ck", "wildcard");
+        assert_eq!(list.public_suffix("a.example.ck"), "example.ck", "wildcard");
+        assert_eq!(list.public_suffix("www.ck"), "ck", "exception");
+        assert_eq!(list.public_suffix("a.www.ck"), "ck", "exception");
+        assert_eq!(list.public_suffix("example.unknown"), "unknown", "default");
+        assert_eq!(list.public_suffix("example.com."), "com", "trailing dot");
+
+        assert_eq!(list.registrable_domain("a.www.ck"), Some("www.ck"));
+        assert_eq!(list.registrable_domain("example.ck"), None);
+        assert_eq!(list.registrable_domain("localhost"), None);
+    }
+}
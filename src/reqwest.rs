@@ -0,0 +1,41 @@
+//! Integration with `reqwest`, as its cookie store.
+//!
+//! `SharedCookieJar` implements `reqwest::cookie::CookieStore`, so a client
+//! stores the cookies of its responses and sends them back following
+//! RFC 6265, including public suffix checks and `Secure` cookies.
+//!
+//! Requires the `reqwest` feature.
+//!
+//! # Example
+//!
+//! ```
+//! use std::sync::Arc;
+//!
+//! use cookies::SharedCookieJar;
+//!
+//! let jar = SharedCookieJar::new();
+//! let client = reqwest::Client::builder()
+//!     .cookie_provider(Arc::new(jar.clone()))
+//!     .build()
+//!     .unwrap();
+//! ```
+
+use http::HeaderValue;
+use reqwest::cookie::CookieStore;
+use url::Url;
+
+use crate::client::store_cookies;
+use crate::{RequestContext, SharedCookieJar};
+
+impl CookieStore for SharedCookieJar {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+        store_cookies(self, url, cookie_headers);
+    }
+
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        // reqwest doesn't know the site a request is made from, so it is
+        // treated as same-site.
+        let header = self.cookie_header(url, &RequestContext::new())?;
+        HeaderValue::from_str(&header).ok()
+    }
+}
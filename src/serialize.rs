@@ -0,0 +1,439 @@
+use std::fmt;
+use std::io;
+use std::time::{Duration, SystemTime};
+
+use super::Cookie;
+use crate::util;
+
+/// Options for how a `Cookie` is written as a `Set-Cookie` string.
+///
+/// The defaults match the `Display` output of cookies: attributes are
+/// written in the order `Path`, `Domain`, `Max-Age`, `Expires`, `HttpOnly`,
+/// `Secure`, `SameSite`, `Priority`, `Partitioned`, and `Expires` is
+/// included next to `Max-Age`.
+///
+/// Attribute names are always written in the casing of RFC 6265, such as
+/// `HttpOnly`, since they are matched case-insensitively. There is no
+/// option to change it.
+///
+/// # Example
+///
+/// ```
+/// use cookies::{Attribute, Builder, Cookie, Expiry, SerializeOptions};
+///
+/// let c = Builder::new("foo", "bar")
+///     .path("/")
+///     .secure(true)
+///     .max_age(std::time::Duration::from_secs(60))
+///     .build()
+///     .unwrap();
+///
+/// let opts = SerializeOptions::new()
+///     .expiry(Expiry::MAX_AGE)
+///     .order(&[Attribute::SECURE]);
+///
+/// assert_eq!(c.display_with(&opts).to_string(), "foo=bar; Secure; Path=/; Max-Age=60");
+/// ```
+#[derive(Clone, Debug)]
+pub struct SerializeOptions {
+    expiry: Expiry,
+    order: [Attribute; ATTRIBUTES],
+    domain_leading_dot: bool,
+}
+
+/// Which attributes are written for the expiry of a cookie.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Expiry(ExpiryRepr);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+enum ExpiryRepr {
+    MaxAge,
+    Expires,
+    Both,
+}
+
+/// An attribute of a `Set-Cookie` string, used to pick their order.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Attribute(AttributeRepr);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+enum AttributeRepr {
+    Path,
+    Domain,
+    MaxAge,
+    Expires,
+    HttpOnly,
+    Secure,
+    SameSite,
+    Priority,
+    Partitioned,
+}
+
+const ATTRIBUTES: usize = 9;
+
+const DEFAULT_ORDER: [Attribute; ATTRIBUTES] = [
+    Attribute::PATH,
+    Attribute::DOMAIN,
+    Attribute::MAX_AGE,
+    Attribute::EXPIRES,
+    Attribute::HTTP_ONLY,
+    Attribute::SECURE,
+    Attribute::SAME_SITE,
+    Attribute::PRIORITY,
+    Attribute::PARTITIONED,
+];
+
+pub(crate) static DEFAULT: SerializeOptions = SerializeOptions::new();
+
+/// A `Display` adapter for a `Cookie` using `SerializeOptions`.
+///
+/// Created by `Cookie::display_with`.
+pub struct DisplayWith<'a> {
+    cookie: &'a dyn Cookie,
+    opts: &'a SerializeOptions,
+}
+
+// ===== impl SerializeOptions =====
+
+impl SerializeOptions {
+    /// Create the default `SerializeOptions`.
+    pub const fn new() -> SerializeOptions {
+        SerializeOptions {
+            expiry: Expiry::BOTH,
+            order: DEFAULT_ORDER,
+            domain_leading_dot: false,
+        }
+    }
+
+    /// Set which attributes are written for the expiry of a cookie.
+    pub fn expiry(mut self, expiry: Expiry) -> SerializeOptions {
+        self.expiry = expiry;
+        self
+    }
+
+    /// Set the order attributes are written in.
+    ///
+    /// The attributes in `first` are written first, in that order. Any other
+    /// attributes follow in their default order.
+    pub fn order(mut self, first: &[Attribute]) -> SerializeOptions {
+        let mut order = Vec::with_capacity(ATTRIBUTES);
+        for &attr in first.iter().chain(DEFAULT_ORDER.iter()) {
+            if !order.contains(&attr) {
+                order.push(attr);
+            }
+        }
+        self.order.copy_from_slice(&order);
+        self
+    }
+
+    /// Write the `Domain` attribute with a leading dot.
+    ///
+    /// RFC 6265 ignores a leading dot, but some ancient user-agents need it
+    /// to send the cookie to subdomains.
+    pub fn domain_leading_dot(mut self, enabled: bool) -> SerializeOptions {
+        self.domain_leading_dot = enabled;
+        self
+    }
+
+    /// Get the length of the `Set-Cookie` string of a `Cookie` with these
+    /// options, in bytes.
+    pub fn serialized_len(&self, cookie: &dyn Cookie) -> usize {
+        let mut counter = Counter(0);
+        write(cookie, self, &mut counter).expect("Counter never fails");
+        counter.0
+    }
+
+    /// Write the `Set-Cookie` string of a `Cookie` with these options into
+    /// some bytes.
+    pub fn write_set_cookie<W: io::Write + ?Sized>(
+        &self,
+        cookie: &dyn Cookie,
+        dst: &mut W,
+    ) -> io::Result<()> {
+        let mut writer = IoWriter {
+            inner: dst,
+            error: None,
+        };
+        match write(cookie, self, &mut writer) {
+            Ok(()) => Ok(()),
+            Err(fmt::Error) => Err(writer
+                .error
+                .unwrap_or_else(|| io::Error::other("formatter error"))),
+        }
+    }
+}
+
+impl Default for SerializeOptions {
+    fn default() -> SerializeOptions {
+        SerializeOptions::new()
+    }
+}
+
+// ===== impl Expiry =====
+
+impl Expiry {
+    /// Only write `Max-Age`.
+    pub const MAX_AGE: Expiry = Expiry(ExpiryRepr::MaxAge);
+    /// Only write `Expires`.
+    pub const EXPIRES: Expiry = Expiry(ExpiryRepr::Expires);
+    /// Write both `Max-Age` and `Expires`, for user-agents that don't
+    /// support `Max-Age`.
+    pub const BOTH: Expiry = Expiry(ExpiryRepr::Both);
+}
+
+impl fmt::Debug for Expiry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+// ===== impl Attribute =====
+
+impl Attribute {
+    /// The `Path` attribute.
+    pub const PATH: Attribute = Attribute(AttributeRepr::Path);
+    /// The `Domain` attribute.
+    pub const DOMAIN: Attribute = Attribute(AttributeRepr::Domain);
+    /// The `Max-Age` attribute.
+    pub const MAX_AGE: Attribute = Attribute(AttributeRepr::MaxAge);
+    /// The `Expires` attribute.
+    pub const EXPIRES: Attribute = Attribute(AttributeRepr::Expires);
+    /// The `HttpOnly` attribute.
+    pub const HTTP_ONLY: Attribute = Attribute(AttributeRepr::HttpOnly);
+    /// The `Secure` attribute.
+    pub const SECURE: Attribute = Attribute(AttributeRepr::Secure);
+    /// The `SameSite` attribute.
+    pub const SAME_SITE: Attribute = Attribute(AttributeRepr::SameSite);
+    /// The `Priority` attribute.
+    pub const PRIORITY: Attribute = Attribute(AttributeRepr::Priority);
+    /// The `Partitioned` attribute.
+    pub const PARTITIONED: Attribute = Attribute(AttributeRepr::Partitioned);
+}
+
+impl fmt::Debug for Attribute {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+// ===== impl DisplayWith =====
+
+impl<'a> DisplayWith<'a> {
+    pub(crate) fn new(cookie: &'a dyn Cookie, opts: &'a SerializeOptions) -> DisplayWith<'a> {
+        DisplayWith { cookie, opts }
+    }
+}
+
+impl<'a> fmt::Debug for DisplayWith<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DisplayWith")
+            .field("cookie", &self.cookie)
+            .field("opts", &self.opts)
+            .finish()
+    }
+}
+
+impl<'a> fmt::Display for DisplayWith<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write(self.cookie, self.opts, f)
+    }
+}
+
+// ===== writers =====
+
+struct Counter(usize);
+
+impl fmt::Write for Counter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+/// Adapts an `io::Write` to be used as a `fmt::Write`, keeping any error.
+struct IoWriter<'a, W: ?Sized> {
+    inner: &'a mut W,
+    error: Option<io::Error>,
+}
+
+impl<'a, W: io::Write + ?Sized> fmt::Write for IoWriter<'a, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+pub(crate) fn write<W: fmt::Write>(
+    cookie: &dyn Cookie,
+    opts: &SerializeOptions,
+    f: &mut W,
+) -> fmt::Result {
+    f.write_str(cookie.name())?;
+    f.write_str("=")?;
+    f.write_str(cookie.value())?;
+
+    for attr in &opts.order {
+        write_attribute(cookie, opts, *attr, f)?;
+    }
+
+    Ok(())
+}
+
+fn write_attribute<W: fmt::Write>(
+    cookie: &dyn Cookie,
+    opts: &SerializeOptions,
+    attr: Attribute,
+    f: &mut W,
+) -> fmt::Result {
+    match attr.0 {
+        AttributeRepr::Path => {
+            if let Some(path) = cookie.path() {
+                f.write_str("; Path=")?;
+                f.write_str(path)?;
+            }
+        }
+        AttributeRepr::Domain => {
+            if let Some(domain) = cookie.domain() {
+                f.write_str("; Domain=")?;
+                if opts.domain_leading_dot {
+                    f.write_str(".")?;
+                }
+                f.write_str(domain)?;
+            }
+        }
+        AttributeRepr::MaxAge => {
+            if opts.expiry == Expiry::EXPIRES {
+                return Ok(());
+            }
+            let max_age = cookie.max_age().or_else(|| {
+                // Without Expires, the expiry must be written as Max-Age.
+                if opts.expiry == Expiry::MAX_AGE {
+                    let expires = cookie.expires()?;
+                    Some(
+                        expires
+                            .duration_since(SystemTime::now())
+                            .unwrap_or(Duration::from_secs(0)),
+                    )
+                } else {
+                    None
+                }
+            });
+            if let Some(ma) = max_age {
+                f.write_str("; Max-Age=")?;
+                write!(f, "{}", ma.as_secs())?;
+            }
+        }
+        AttributeRepr::Expires => {
+            if opts.expiry == Expiry::MAX_AGE {
+                return Ok(());
+            }
+            // A pinned Expires is written as-is, otherwise it's calculated
+            // from Max-Age.
+            let expires = match (cookie.expires(), cookie.max_age()) {
+                (Some(expires), _) => Some(util::to_tm(expires)),
+                (None, Some(ma)) => Some(util::get_expires(ma)),
+                (None, None) => None,
+            };
+            if let Some(expires) = expires {
+                f.write_str("; Expires=")?;
+                write!(f, "{}", expires.rfc822())?;
+            }
+        }
+        AttributeRepr::HttpOnly => {
+            if cookie.http_only() {
+                f.write_str("; HttpOnly")?;
+            }
+        }
+        AttributeRepr::Secure => {
+            if cookie.secure() {
+                f.write_str("; Secure")?;
+            }
+        }
+        AttributeRepr::SameSite => {
+            if let Some(same_site) = cookie.same_site() {
+                f.write_str("; SameSite=")?;
+                f.write_str(same_site.as_str())?;
+            }
+        }
+        AttributeRepr::Priority => {
+            if let Some(priority) = cookie.priority() {
+                f.write_str("; Priority=")?;
+                f.write_str(priority.as_str())?;
+            }
+        }
+        AttributeRepr::Partitioned => {
+            if cookie.partitioned() {
+                f.write_str("; Partitioned")?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::Builder;
+
+    fn cookie() -> impl Cookie {
+        Builder::new("foo", "bar")
+            .path("/")
+            .domain("hyper.rs")
+            .max_age(Duration::from_secs(100))
+            .secure(true)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn default_matches_display() {
+        let c = cookie();
+        let opts = SerializeOptions::new();
+        assert_eq!(c.display_with(&opts).to_string(), c.to_string());
+    }
+
+    #[test]
+    fn expiry() {
+        let c = cookie();
+
+        let opts = SerializeOptions::new().expiry(Expiry::MAX_AGE);
+        let s = c.display_with(&opts).to_string();
+        assert_eq!(s, "foo=bar; Path=/; Domain=hyper.rs; Max-Age=100; Secure");
+
+        let opts = SerializeOptions::new().expiry(Expiry::EXPIRES);
+        let s = c.display_with(&opts).to_string();
+        assert!(s.starts_with("foo=bar; Path=/; Domain=hyper.rs; Expires="));
+        assert!(s.ends_with(" GMT; Secure"));
+        assert!(!s.contains("Max-Age"));
+    }
+
+    #[test]
+    fn order() {
+        let c = cookie();
+        let opts = SerializeOptions::new().expiry(Expiry::MAX_AGE).order(&[
+            Attribute::SECURE,
+            Attribute::DOMAIN,
+            Attribute::SECURE,
+        ]);
+
+        let s = c.display_with(&opts).to_string();
+        assert_eq!(s, "foo=bar; Secure; Domain=hyper.rs; Path=/; Max-Age=100");
+    }
+
+    #[test]
+    fn domain_leading_dot() {
+        let c = cookie();
+        let opts = SerializeOptions::new()
+            .expiry(Expiry::MAX_AGE)
+            .domain_leading_dot(true);
+
+        let s = c.display_with(&opts).to_string();
+        assert_eq!(s, "foo=bar; Path=/; Domain=.hyper.rs; Max-Age=100; Secure");
+        assert_eq!(opts.serialized_len(&c), s.len());
+    }
+}
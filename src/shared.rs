@@ -0,0 +1,177 @@
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use url::Url;
+
+use crate::{Cookie, CookieJar, Error, OwnedCookie, RequestContext, StoredCookie};
+
+/// A `CookieJar` shared between threads.
+///
+/// Cloning a `SharedCookieJar` gives another handle to the same jar, so a
+/// client can use one session from many tasks. Getting the cookies of a
+/// request only reads the jar, so requests don't block each other, while
+/// storing cookies waits for them and is seen by all of them at once.
+///
+/// # Example
+///
+/// ```
+/// use cookies::{RequestContext, SharedCookieJar};
+/// use url::Url;
+///
+/// let url = Url::parse("https://hyper.rs/").unwrap();
+/// let jar = SharedCookieJar::new();
+///
+/// let (handle, response_url) = (jar.clone(), url.clone());
+/// std::thread::spawn(move || {
+///     handle.store_set_cookies(&response_url, ["a=1", "b=2"]);
+/// })
+/// .join()
+/// .unwrap();
+///
+/// let header = jar.cookie_header(&url, &RequestContext::new());
+/// assert_eq!(header.as_deref(), Some("a=1; b=2"));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SharedCookieJar {
+    inner: Arc<RwLock<CookieJar>>,
+}
+
+// ===== impl SharedCookieJar =====
+
+impl SharedCookieJar {
+    /// Create an empty `SharedCookieJar`.
+    pub fn new() -> SharedCookieJar {
+        SharedCookieJar::default()
+    }
+
+    /// Store a cookie received in a response to a request for `url`.
+    ///
+    /// See `CookieJar::store`.
+    pub fn store(&self, url: &Url, cookie: &dyn Cookie) -> Result<(), Error> {
+        self.write().store(url, cookie)
+    }
+
+    /// Store a cookie received in a response to a request for `url`, made
+    /// in some context.
+    ///
+    /// See `CookieJar::store_with_context`.
+    pub fn store_with_context(
+        &self,
+        url: &Url,
+        cookie: &dyn Cookie,
+        ctx: &RequestContext,
+    ) -> Result<(), Error> {
+        self.write().store_with_context(url, cookie, ctx)
+    }
+
+    /// Parse a `Set-Cookie` header received in a response to a request for
+    /// `url`, and store the cookie.
+    ///
+    /// See `CookieJar::store_set_cookie`.
+    pub fn store_set_cookie(&self, url: &Url, header: &str) -> Result<(), Error> {
+        self.write().store_set_cookie(url, header)
+    }
+
+    /// Store the cookies of all the `Set-Cookie` headers of a response to a
+    /// request for `url`, at once.
+    ///
+    /// No request sees some of the cookies but not the others. Headers that
+    /// don't parse, or whose cookie is rejected, are skipped as a user-agent
+    /// would ignore them.
+    pub fn store_set_cookies<I>(&self, url: &Url, headers: I)
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        self.store_set_cookies_with_context(url, headers, &RequestContext::new());
+    }
+
+    /// Store the cookies of all the `Set-Cookie` headers of a response to a
+    /// request for `url` made in some context, at once.
+    ///
+    /// See `store_set_cookies`.
+    pub fn store_set_cookies_with_context<I>(&self, url: &Url, headers: I, ctx: &RequestContext)
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        // Parse before taking the lock, to hold it as briefly as possible.
+        let cookies = headers
+            .into_iter()
+            .filter_map(|header| {
+                crate::parse(header.as_ref())
+                    .ok()
+                    .map(|c| OwnedCookie::from(&c))
+            })
+            .collect::<Vec<_>>();
+
+        let mut jar = self.write();
+        for cookie in &cookies {
+            let _ = jar.store_with_context(url, cookie, ctx);
+        }
+    }
+
+    /// Insert a cookie as it is, such as one imported from a file.
+    ///
+    /// See `CookieJar::insert`.
+    pub fn insert(&self, cookie: StoredCookie) -> Result<(), Error> {
+        self.write().insert(cookie)
+    }
+
+    /// Get the value of the `Cookie` header to send with a request for
+    /// `url`, or `None` if there are no cookies to send.
+    ///
+    /// See `CookieJar::cookie_header`.
+    pub fn cookie_header(&self, url: &Url, ctx: &RequestContext) -> Option<String> {
+        self.read().cookie_header(url, ctx)
+    }
+
+    /// Lock the jar for reading, waiting for any thread storing cookies.
+    ///
+    /// A panic while the jar was locked doesn't poison it, since every
+    /// change to the jar leaves it valid.
+    pub fn read(&self) -> RwLockReadGuard<'_, CookieJar> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Lock the jar for writing, waiting for any other thread using it.
+    ///
+    /// See `read`.
+    pub fn write(&self) -> RwLockWriteGuard<'_, CookieJar> {
+        self.inner.write().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl From<CookieJar> for SharedCookieJar {
+    fn from(jar: CookieJar) -> SharedCookieJar {
+        SharedCookieJar {
+            inner: Arc::new(RwLock::new(jar)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SharedCookieJar>();
+    }
+
+    #[test]
+    fn clones_share_the_jar() {
+        let url = Url::parse("https://hyper.rs/").unwrap();
+        let jar = SharedCookieJar::from(CookieJar::new().max_cookies(1));
+        let other = jar.clone();
+
+        other.store_set_cookie(&url, "a=1").unwrap();
+        jar.store_set_cookies(&url, ["b=2", "bad cookie"]);
+
+        assert_eq!(other.read().len(), 1, "limit of the jar applies");
+        assert_eq!(
+            jar.cookie_header(&url, &RequestContext::new()).as_deref(),
+            Some("b=2")
+        );
+    }
+}
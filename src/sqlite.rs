@@ -0,0 +1,24 @@
+use std::io;
+use std::path::Path;
+
+use rusqlite::{Connection, OpenFlags};
+
+/// Open a browser's cookie database read-only.
+///
+/// It is opened as immutable, so that the lock the browser holds on it while
+/// running is ignored. Anything the browser hasn't written yet is missing.
+pub(crate) fn open(path: &Path) -> Result<Connection, OpenError> {
+    let path = path.canonicalize().map_err(OpenError::Io)?;
+    let mut uri = url::Url::from_file_path(&path).expect("canonical path is absolute");
+    uri.set_query(Some("immutable=1"));
+    Connection::open_with_flags(
+        uri.as_str(),
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_URI,
+    )
+    .map_err(OpenError::Sqlite)
+}
+
+pub(crate) enum OpenError {
+    Io(io::Error),
+    Sqlite(rusqlite::Error),
+}
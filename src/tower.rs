@@ -0,0 +1,273 @@
+//! Integration with `tower`, as a client middleware.
+//!
+//! `CookieLayer` wraps a client service, such as a `hyper-util` client, to
+//! add a `Cookie` header to each request from a jar, and store the cookies
+//! of each response. A caller following redirects sends each of them
+//! through the service, so cookies set by redirects are stored too.
+//!
+//! Requires the `tower` feature.
+//!
+//! # Example
+//!
+//! ```
+//! use cookies::tower::CookieLayer;
+//! use cookies::SharedCookieJar;
+//! use tower_layer::Layer;
+//!
+//! # fn wrap<S>(client: S) {
+//! let jar = SharedCookieJar::new();
+//! let client = CookieLayer::new(jar.clone()).layer(client);
+//! # }
+//! ```
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use http::header::{HOST, SET_COOKIE};
+use http::{Request, Response};
+use pin_project_lite::pin_project;
+use tower_layer::Layer;
+use tower_service::Service;
+use url::Url;
+
+use crate::client::{add_cookies, store_cookies};
+use crate::SharedCookieJar;
+
+/// A `Layer` that wraps services in a `CookieService`.
+#[derive(Clone, Debug)]
+pub struct CookieLayer {
+    jar: SharedCookieJar,
+}
+
+/// A client `Service` that sends and stores the cookies of a jar.
+///
+/// `Cookie` headers already set on a request are kept, joined into one with
+/// the cookies of the jar appended. A request with a `Cookie` header that
+/// isn't ASCII is sent as is. Requests with a URI that isn't absolute use
+/// the `Host` header and `http`.
+#[derive(Clone, Debug)]
+pub struct CookieService<S> {
+    inner: S,
+    jar: SharedCookieJar,
+}
+
+pin_project! {
+    /// The response future of a `CookieService`.
+    #[derive(Debug)]
+    pub struct ResponseFuture<F> {
+        #[pin]
+        inner: F,
+        jar: SharedCookieJar,
+        url: Option<Url>,
+    }
+}
+
+fn request_url<B>(request: &Request<B>) -> Option<Url> {
+    let uri = request.uri();
+    if uri.scheme().is_some() {
+        return Url::parse(&uri.to_string()).ok();
+    }
+
+    let host = request.headers().get(HOST)?.to_str().ok()?;
+    let path = uri.path_and_query().map_or("/", |p| p.as_str());
+    Url::parse(&format!("http://{}{}", host, path)).ok()
+}
+
+// ===== impl CookieLayer =====
+
+impl CookieLayer {
+    /// Create a layer for a jar.
+    pub fn new(jar: SharedCookieJar) -> CookieLayer {
+        CookieLayer { jar }
+    }
+}
+
+impl<S> Layer<S> for CookieLayer {
+    type Service = CookieService<S>;
+
+    fn layer(&self, inner: S) -> CookieService<S> {
+        CookieService::new(inner, self.jar.clone())
+    }
+}
+
+// ===== impl CookieService =====
+
+impl<S> CookieService<S> {
+    /// Wrap a service with a jar.
+    pub fn new(inner: S, jar: SharedCookieJar) -> CookieService<S> {
+        CookieService { inner, jar }
+    }
+
+    /// Get the jar of this service.
+    pub fn jar(&self) -> &SharedCookieJar {
+        &self.jar
+    }
+
+    /// Get a reference to the inner service.
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the inner service.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Consume this service, returning the inner one.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for CookieService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+{
+    type Response = Response<ResBody>;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<ReqBody>) -> Self::Future {
+        let url = request_url(&request);
+
+        if let Some(ref url) = url {
+            add_cookies(&self.jar, url, request.headers_mut());
+        }
+
+        ResponseFuture {
+            inner: self.inner.call(request),
+            jar: self.jar.clone(),
+            url,
+        }
+    }
+}
+
+// ===== impl ResponseFuture =====
+
+impl<F, B, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<Response<B>, E>>,
+{
+    type Output = Result<Response<B>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let response = ready!(this.inner.poll(cx))?;
+
+        if let Some(url) = this.url.take() {
+            store_cookies(
+                this.jar,
+                &url,
+                response.headers().get_all(SET_COOKIE).iter(),
+            );
+        }
+        Poll::Ready(Ok(response))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+    use std::future::{ready, Ready};
+    use std::sync::Arc;
+    use std::task::{Wake, Waker};
+
+    use http::header::COOKIE;
+
+    use super::*;
+
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    /// A service that responds with the `Cookie` header of the request as a
+    /// `Set-Cookie`, and a fixed `Set-Cookie`.
+    struct Echo;
+
+    impl Service<Request<()>> for Echo {
+        type Response = Response<String>;
+        type Error = Infallible;
+        type Future = Ready<Result<Response<String>, Infallible>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: Request<()>) -> Self::Future {
+            let cookie = request
+                .headers()
+                .get(COOKIE)
+                .map(|v| v.to_str().unwrap_or("opaque").to_owned())
+                .unwrap_or_default();
+            let response = Response::builder()
+                .header(SET_COOKIE, "a=1; Path=/")
+                .header(SET_COOKIE, "bad cookie")
+                .body(cookie)
+                .unwrap();
+            ready(Ok(response))
+        }
+    }
+
+    fn call(svc: &mut CookieService<Echo>, request: Request<()>) -> String {
+        let mut future = Box::pin(svc.call(request));
+        let waker = Waker::from(Arc::new(NoopWaker));
+        match future.as_mut().poll(&mut Context::from_waker(&waker)) {
+            Poll::Ready(Ok(response)) => response.into_body(),
+            _ => unreachable!("Echo is always ready"),
+        }
+    }
+
+    #[test]
+    fn sends_and_stores() {
+        let jar = SharedCookieJar::new();
+        let mut svc = CookieLayer::new(jar.clone()).layer(Echo);
+
+        let get = || Request::get("http://hyper.rs/").body(()).unwrap();
+        assert_eq!(call(&mut svc, get()), "");
+        assert_eq!(call(&mut svc, get()), "a=1");
+        assert_eq!(jar.read().len(), 1);
+
+        let manual = Request::get("http://hyper.rs/")
+            .header(COOKIE, "b=2")
+            .body(())
+            .unwrap();
+        assert_eq!(call(&mut svc, manual), "b=2; a=1");
+
+        let several = Request::get("http://hyper.rs/")
+            .header(COOKIE, "b=2")
+            .header(COOKIE, "c=3")
+            .body(())
+            .unwrap();
+        assert_eq!(call(&mut svc, several), "b=2; c=3; a=1", "joined");
+
+        let opaque = Request::get("http://hyper.rs/")
+            .header(COOKIE, &b"b=\xff"[..])
+            .body(())
+            .unwrap();
+        assert_eq!(call(&mut svc, opaque), "opaque", "left as is");
+    }
+
+    #[test]
+    fn origin_form_uses_host() {
+        let jar = SharedCookieJar::new();
+        let mut svc = CookieService::new(Echo, jar.clone());
+
+        let request = Request::get("/guides")
+            .header(HOST, "www.hyper.rs")
+            .body(())
+            .unwrap();
+        call(&mut svc, request);
+        assert!(svc.jar().read().get("www.hyper.rs", "/", "a").is_some());
+
+        let request = Request::get("/").body(()).unwrap();
+        call(&mut svc, request);
+        assert_eq!(jar.read().len(), 1, "no URL to store for");
+    }
+}
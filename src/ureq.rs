@@ -0,0 +1,224 @@
+//! Integration with `ureq`, as a middleware or an agent.
+//!
+//! `CookieMiddleware` adds the cookies of a jar to each request of a
+//! `ureq::Agent`, and stores the cookies of each response. ureq follows
+//! redirects, and turns error statuses into errors, inside of its
+//! middleware, so cookies set by those responses are missed. `CookieAgent`
+//! wraps an agent to do both itself instead, storing the cookies of every
+//! response.
+//!
+//! Requires the `ureq` feature.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use cookies::ureq::CookieAgent;
+//! use cookies::SharedCookieJar;
+//!
+//! let jar = SharedCookieJar::new();
+//! let agent = CookieAgent::new(jar.clone());
+//!
+//! let mut res = agent.get("http://hyper.rs/login").unwrap();
+//! let body = res.body_mut().read_to_string().unwrap();
+//!
+//! println!("stored {} cookies", jar.read().len());
+//! ```
+
+use ureq::http::header::{
+    AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, COOKIE, LOCATION, SET_COOKIE, TRANSFER_ENCODING,
+};
+use ureq::http::{Method, Request, Response, StatusCode};
+use ureq::middleware::{Middleware, MiddlewareNext};
+use ureq::{Agent, AsSendBody, Body, ResponseExt, SendBody};
+use url::Url;
+
+use crate::client::{add_cookies, store_cookies};
+use crate::SharedCookieJar;
+
+/// A `ureq` middleware that sends and stores the cookies of a jar.
+///
+/// `Cookie` headers already set on a request are kept, joined into one with
+/// the cookies of the jar appended. A request with a `Cookie` header that
+/// isn't ASCII is sent as is. The cookies of a response are stored for its
+/// own URL, which is the last one when ureq followed redirects.
+#[derive(Clone, Debug)]
+pub struct CookieMiddleware {
+    jar: SharedCookieJar,
+}
+
+/// A `ureq::Agent` that sends and stores the cookies of a jar, including
+/// for redirects and error responses.
+#[derive(Clone, Debug)]
+pub struct CookieAgent {
+    agent: Agent,
+    jar: SharedCookieJar,
+}
+
+/// Check if a request may have a body, which can't be sent again.
+fn may_have_body(method: &Method) -> bool {
+    ![
+        Method::GET,
+        Method::HEAD,
+        Method::OPTIONS,
+        Method::TRACE,
+        Method::DELETE,
+    ]
+    .contains(method)
+}
+
+fn to_url(uri: &ureq::http::Uri) -> Result<Url, ureq::Error> {
+    Url::parse(&uri.to_string()).map_err(|err| ureq::Error::BadUri(err.to_string()))
+}
+
+// ===== impl CookieMiddleware =====
+
+impl CookieMiddleware {
+    /// Create a middleware for a jar.
+    pub fn new(jar: SharedCookieJar) -> CookieMiddleware {
+        CookieMiddleware { jar }
+    }
+}
+
+impl Middleware for CookieMiddleware {
+    fn handle(
+        &self,
+        mut request: Request<SendBody>,
+        next: MiddlewareNext,
+    ) -> Result<Response<Body>, ureq::Error> {
+        let url = to_url(request.uri())?;
+        add_cookies(&self.jar, &url, request.headers_mut());
+
+        // ureq follows redirects inside of `next`, so the response may be
+        // from another URL than the request.
+        let response = next.handle(request)?;
+        let url = to_url(response.get_uri())?;
+        store_cookies(
+            &self.jar,
+            &url,
+            response.headers().get_all(SET_COOKIE).iter(),
+        );
+        Ok(response)
+    }
+}
+
+// ===== impl CookieAgent =====
+
+impl CookieAgent {
+    /// Create an agent with ureq's default config.
+    pub fn new(jar: SharedCookieJar) -> CookieAgent {
+        CookieAgent::with_agent(Agent::new_with_defaults(), jar)
+    }
+
+    /// Wrap an agent, keeping its config.
+    ///
+    /// Redirects are followed up to the `max_redirects` of the config, and
+    /// error statuses are errors if `http_status_as_error` is set.
+    pub fn with_agent(agent: Agent, jar: SharedCookieJar) -> CookieAgent {
+        CookieAgent { agent, jar }
+    }
+
+    /// Get the jar of this agent.
+    pub fn jar(&self) -> &SharedCookieJar {
+        &self.jar
+    }
+
+    /// Make a `GET` request to a URL.
+    ///
+    /// # Error
+    ///
+    /// Returns an error as in `run`.
+    pub fn get(&self, url: &str) -> Result<Response<Body>, ureq::Error> {
+        self.run(Request::get(url).body(())?)
+    }
+
+    /// Run a request, following redirects.
+    ///
+    /// A redirect that changes the method to `GET`, as `303 See Other`
+    /// does, or a `301` or `302` does for a `POST`, is followed without the
+    /// body. Any other redirect of a request that may have a body, such as a
+    /// `PUT`, is returned as the response, since the body can't be sent
+    /// again.
+    ///
+    /// # Error
+    ///
+    /// Returns any error from ureq, `TooManyRedirects` if there are more
+    /// redirects than the maximum, or `StatusCode` for an error status.
+    pub fn run(&self, request: Request<impl AsSendBody>) -> Result<Response<Body>, ureq::Error> {
+        let config = self.agent.config();
+        let mut method = request.method().clone();
+        let mut url = to_url(request.uri())?;
+
+        // The headers to send again with redirects.
+        let mut headers = request.headers().clone();
+        for name in [
+            COOKIE,
+            AUTHORIZATION,
+            CONTENT_LENGTH,
+            CONTENT_TYPE,
+            TRANSFER_ENCODING,
+        ] {
+            headers.remove(name);
+        }
+
+        let mut response = self.send(request, &url)?;
+        let mut redirects = 0;
+        while response.status().is_redirection() && config.max_redirects() > 0 {
+            let location = match response.headers().get(LOCATION) {
+                Some(location) => location.to_str().map_err(|_| ureq::Error::RedirectFailed)?,
+                None => break,
+            };
+
+            method = match response.status() {
+                StatusCode::SEE_OTHER if method != Method::HEAD => Method::GET,
+                StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND if method == Method::POST => {
+                    Method::GET
+                }
+                _ if may_have_body(&method) => break,
+                _ => method,
+            };
+
+            if redirects == config.max_redirects() {
+                return Err(ureq::Error::TooManyRedirects);
+            }
+            redirects += 1;
+
+            url = url
+                .join(location)
+                .map_err(|err| ureq::Error::BadUri(err.to_string()))?;
+            let mut next = Request::builder().method(method.clone()).uri(url.as_str());
+            if let Some(next_headers) = next.headers_mut() {
+                next_headers.extend(headers.clone());
+            }
+            response = self.send(next.body(())?, &url)?;
+        }
+
+        let status = response.status();
+        if config.http_status_as_error() && (status.is_client_error() || status.is_server_error()) {
+            return Err(ureq::Error::StatusCode(status.as_u16()));
+        }
+        Ok(response)
+    }
+
+    /// Send a single request, without following redirects.
+    fn send(
+        &self,
+        mut request: Request<impl AsSendBody>,
+        url: &Url,
+    ) -> Result<Response<Body>, ureq::Error> {
+        add_cookies(&self.jar, url, request.headers_mut());
+        let request = self
+            .agent
+            .configure_request(request)
+            .max_redirects(0)
+            .http_status_as_error(false)
+            .build();
+
+        let response = self.agent.run(request)?;
+        store_cookies(
+            &self.jar,
+            url,
+            response.headers().get_all(SET_COOKIE).iter(),
+        );
+        Ok(response)
+    }
+}
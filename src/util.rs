@@ -1,7 +1,11 @@
+use std::convert::TryFrom;
 use std::fmt;
-use std::time::Duration;
+use std::io;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use super::{Cookie, Sealed};
+use super::{Cookie, Error, Sealed};
+use crate::serialize;
 
 /// Value representing the `SameSite` cookie attribute.
 #[derive(Copy, Clone, PartialEq, Eq, Hash)]
@@ -11,6 +15,7 @@ pub struct SameSite(SameSiteRepr);
 enum SameSiteRepr {
     Lax,
     Strict,
+    None,
     __NonExhaustive,
 }
 
@@ -19,6 +24,23 @@ impl SameSite {
     pub const LAX: SameSite = SameSite(SameSiteRepr::Lax);
     /// `SameSite=Strict`
     pub const STRICT: SameSite = SameSite(SameSiteRepr::Strict);
+    /// `SameSite=None`
+    ///
+    /// The cookie is explicitly sent with cross-site requests.
+    pub const NONE: SameSite = SameSite(SameSiteRepr::None);
+
+    #[cfg(feature = "serde")]
+    pub(crate) const VARIANTS: &'static [&'static str] = &["Strict", "Lax", "None"];
+
+    /// Get the value of the attribute, such as `"Lax"`.
+    pub fn as_str(&self) -> &'static str {
+        match self.0 {
+            SameSiteRepr::Lax => "Lax",
+            SameSiteRepr::Strict => "Strict",
+            SameSiteRepr::None => "None",
+            SameSiteRepr::__NonExhaustive => unreachable!(),
+        }
+    }
 }
 
 impl fmt::Debug for SameSite {
@@ -27,6 +49,84 @@ impl fmt::Debug for SameSite {
     }
 }
 
+impl FromStr for SameSite {
+    type Err = Error;
+
+    /// Parse the value of the attribute, ignoring ASCII case.
+    fn from_str(s: &str) -> Result<SameSite, Error> {
+        if s.eq_ignore_ascii_case("lax") {
+            Ok(SameSite::LAX)
+        } else if s.eq_ignore_ascii_case("strict") {
+            Ok(SameSite::STRICT)
+        } else if s.eq_ignore_ascii_case("none") {
+            Ok(SameSite::NONE)
+        } else {
+            Err(Error::invalid_attribute())
+        }
+    }
+}
+
+/// Value representing the `Priority` cookie attribute.
+///
+/// This is a non-standard attribute, used by some user-agents to decide
+/// which cookies to evict first. Priorities are ordered from `LOW` to
+/// `HIGH`, and a cookie without one has `MEDIUM` priority.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Priority(PriorityRepr);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum PriorityRepr {
+    Low,
+    Medium,
+    High,
+    __NonExhaustive,
+}
+
+impl Priority {
+    /// `Priority=Low`
+    pub const LOW: Priority = Priority(PriorityRepr::Low);
+    /// `Priority=Medium`
+    pub const MEDIUM: Priority = Priority(PriorityRepr::Medium);
+    /// `Priority=High`
+    pub const HIGH: Priority = Priority(PriorityRepr::High);
+
+    #[cfg(feature = "serde")]
+    pub(crate) const VARIANTS: &'static [&'static str] = &["Low", "Medium", "High"];
+
+    /// Get the value of the attribute, such as `"High"`.
+    pub fn as_str(&self) -> &'static str {
+        match self.0 {
+            PriorityRepr::Low => "Low",
+            PriorityRepr::Medium => "Medium",
+            PriorityRepr::High => "High",
+            PriorityRepr::__NonExhaustive => unreachable!(),
+        }
+    }
+}
+
+impl fmt::Debug for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for Priority {
+    type Err = Error;
+
+    /// Parse the value of the attribute, ignoring ASCII case.
+    fn from_str(s: &str) -> Result<Priority, Error> {
+        if s.eq_ignore_ascii_case("low") {
+            Ok(Priority::LOW)
+        } else if s.eq_ignore_ascii_case("medium") {
+            Ok(Priority::MEDIUM)
+        } else if s.eq_ignore_ascii_case("high") {
+            Ok(Priority::HIGH)
+        } else {
+            Err(Error::invalid_attribute())
+        }
+    }
+}
+
 pub(crate) struct Delegated<D>(pub(crate) D);
 
 /// A delegate/proxy of the `Cookie` trait.
@@ -58,6 +158,10 @@ pub(crate) trait Delegate {
         self.cookie().max_age()
     }
 
+    fn expires(&self) -> Option<SystemTime> {
+        self.cookie().expires()
+    }
+
     fn http_only(&self) -> bool {
         self.cookie().http_only()
     }
@@ -69,6 +173,14 @@ pub(crate) trait Delegate {
     fn same_site(&self) -> Option<SameSite> {
         self.cookie().same_site()
     }
+
+    fn priority(&self) -> Option<Priority> {
+        self.cookie().priority()
+    }
+
+    fn partitioned(&self) -> bool {
+        self.cookie().partitioned()
+    }
 }
 
 impl<D: Delegate> Cookie for Delegated<D> {
@@ -92,6 +204,10 @@ impl<D: Delegate> Cookie for Delegated<D> {
         self.0.max_age()
     }
 
+    fn expires(&self) -> Option<SystemTime> {
+        self.0.expires()
+    }
+
     fn http_only(&self) -> bool {
         self.0.http_only()
     }
@@ -103,6 +219,14 @@ impl<D: Delegate> Cookie for Delegated<D> {
     fn same_site(&self) -> Option<SameSite> {
         self.0.same_site()
     }
+
+    fn priority(&self) -> Option<Priority> {
+        self.0.priority()
+    }
+
+    fn partitioned(&self) -> bool {
+        self.0.partitioned()
+    }
 }
 
 impl<D: Delegate> Sealed for Delegated<D> {}
@@ -137,6 +261,10 @@ pub(crate) fn debug(cookie: &dyn Cookie, f: &mut fmt::Formatter) -> fmt::Result
         builder.field("max_age", ma);
     }
 
+    if let Some(ref expires) = cookie.expires() {
+        builder.field("expires", expires);
+    }
+
     if cookie.http_only() {
         builder.field("http_only", &true);
     }
@@ -149,75 +277,119 @@ pub(crate) fn debug(cookie: &dyn Cookie, f: &mut fmt::Formatter) -> fmt::Result
         builder.field("same_site", ss);
     }
 
+    if let Some(ref priority) = cookie.priority() {
+        builder.field("priority", priority);
+    }
+
+    if cookie.partitioned() {
+        builder.field("partitioned", &true);
+    }
+
     builder.finish()
 }
 
 pub(crate) fn display(cookie: &dyn Cookie, f: &mut fmt::Formatter) -> fmt::Result {
-    f.write_str(cookie.name())?;
-    f.write_str("=")?;
-    f.write_str(cookie.value())?;
-
-    if let Some(path) = cookie.path() {
-        f.write_str("; Path=")?;
-        f.write_str(path)?;
-    }
-
-    if let Some(domain) = cookie.domain() {
-        f.write_str("; Domain=")?;
-        f.write_str(domain)?;
-    }
+    serialize::write(cookie, &serialize::DEFAULT, f)
+}
 
-    if let Some(ma) = cookie.max_age() {
-        f.write_str("; Max-Age=")?;
-        fmt::Display::fmt(&ma.as_secs(), f)?;
+/// Get the length of the `Set-Cookie` string of a `Cookie`, in bytes.
+///
+/// This is the length of the `Display` output of the cookie, calculated
+/// without allocating it.
+///
+/// # Example
+///
+/// ```
+/// let c = cookies::parse("foo=bar; Path=/").unwrap();
+///
+/// assert_eq!(cookies::serialized_len(&c), "foo=bar; Path=/".len());
+/// ```
+pub fn serialized_len(cookie: &dyn Cookie) -> usize {
+    serialize::DEFAULT.serialized_len(cookie)
+}
 
-        // Include Expires, since some old user-agents don't support max-age
-        let expires = get_expires(ma);
-        f.write_str("; Expires=")?;
-        fmt::Display::fmt(&expires.rfc822(), f)?;
-    }
+/// Write the `Set-Cookie` string of a `Cookie` into some bytes.
+///
+/// This is the same as the `Display` output of the cookie, but written
+/// directly into `dst`, such as a `Vec<u8>`, without allocating a `String`
+/// first. A `bytes::BytesMut` can be written to with `BufMut::writer`.
+///
+/// # Example
+///
+/// ```
+/// let c = cookies::parse("foo=bar; Path=/").unwrap();
+///
+/// let mut dst = Vec::new();
+/// cookies::write_set_cookie(&c, &mut dst).unwrap();
+///
+/// assert_eq!(dst, b"foo=bar; Path=/");
+/// ```
+pub fn write_set_cookie<W: io::Write + ?Sized>(cookie: &dyn Cookie, dst: &mut W) -> io::Result<()> {
+    serialize::DEFAULT.write_set_cookie(cookie, dst)
+}
 
-    if cookie.http_only() {
-        f.write_str("; HttpOnly")?;
+/// Add a duration to a time, saturating at the end of the year 9999, the
+/// latest date an `Expires` attribute can represent.
+pub(crate) fn saturating_add(t: SystemTime, dur: Duration) -> SystemTime {
+    let max = UNIX_EPOCH + Duration::from_secs(253_402_300_799);
+    match t.checked_add(dur) {
+        Some(t) if t < max => t,
+        _ => max,
     }
+}
 
-    if cookie.secure() {
-        f.write_str("; Secure")?;
+/// Seconds since the Unix epoch, saturating at the bounds of `i64`.
+pub(crate) fn to_unix(t: SystemTime) -> i64 {
+    match t.duration_since(UNIX_EPOCH) {
+        Ok(dur) => i64::try_from(dur.as_secs()).unwrap_or(i64::MAX),
+        Err(before) => i64::try_from(before.duration().as_secs()).map_or(i64::MIN, |secs| -secs),
     }
+}
 
-    match cookie.same_site() {
-        Some(SameSite::STRICT) => {
-            f.write_str("; SameSite=Strict")?;
-        },
-        Some(SameSite::LAX) => {
-            f.write_str("; SameSite=Lax")?;
-        },
-        Some(_non_exhaustive) => unreachable!(),
-        None => (),
+/// Convert seconds since the Unix epoch into a time, saturating if it is
+/// too far in the future or past to represent.
+pub(crate) fn from_unix(secs: i64) -> SystemTime {
+    if secs >= 0 {
+        saturating_add(UNIX_EPOCH, Duration::from_secs(secs as u64))
+    } else {
+        UNIX_EPOCH
+            .checked_sub(Duration::from_secs(secs.unsigned_abs()))
+            .unwrap_or(UNIX_EPOCH)
     }
+}
 
-    Ok(())
+pub(crate) fn to_tm(t: SystemTime) -> time::Tm {
+    time::at_utc(time::Timespec::new(to_unix(t), 0))
 }
 
-fn get_expires(dur: Duration) -> time::Tm {
-    let t = if dur.as_secs() > std::i64::MAX as u64 {
-        time::Timespec::new(std::i64::MAX, 0)
-    } else {
-        // Seconds since Unix Epoch...
-        let mut t = time::get_time();
-        // 'as_secs' within i64 thanks to check above
-        // If the add would overflow, just assume the latest
-        // possible time.
-        t.sec = t.sec.saturating_add(dur.as_secs() as i64);
-        t
-    };
-    time::at_utc(t)
+pub(crate) fn get_expires(dur: Duration) -> time::Tm {
+    // If the add would overflow, just assume the latest possible time.
+    to_tm(saturating_add(SystemTime::now(), dur))
 }
 
 #[cfg(test)]
 mod tests {
     use std::time::Duration;
 
+    use super::{Priority, SameSite};
+
+    #[test]
+    fn attribute_strings() {
+        for &ss in &[SameSite::STRICT, SameSite::LAX, SameSite::NONE] {
+            assert_eq!(ss.as_str().parse::<SameSite>().unwrap(), ss);
+        }
+        for &p in &[Priority::LOW, Priority::MEDIUM, Priority::HIGH] {
+            assert_eq!(p.as_str().parse::<Priority>().unwrap(), p);
+        }
+
+        assert_eq!("LAX".parse::<SameSite>().unwrap(), SameSite::LAX);
+        assert_eq!("high".parse::<Priority>().unwrap(), Priority::HIGH);
+        "Sometimes"
+            .parse::<SameSite>()
+            .expect_err("unknown SameSite");
+        "".parse::<Priority>().expect_err("unknown Priority");
+    }
+
     #[test]
     fn display_most_attributes() {
         let orig = "foo=bar; Path=/index.html; Domain=hyper.rs; HttpOnly; Secure; SameSite=Strict";
@@ -238,4 +410,33 @@ mod tests {
         let prefix = "foo=bar; Max-Age=100; Expires=";
         assert!(s.starts_with(prefix));
     }
+
+    #[test]
+    fn write_set_cookie_matches_display() {
+        let c = crate::Builder::new("foo", "bar")
+            .path("/")
+            .max_age(Duration::from_secs(100))
+            .secure(true)
+            .build()
+            .unwrap();
+
+        let mut dst = Vec::new();
+        super::write_set_cookie(&c, &mut dst).unwrap();
+
+        assert_eq!(String::from_utf8(dst).unwrap(), c.to_string());
+    }
+
+    #[test]
+    fn serialized_len_matches_display() {
+        let c = crate::Builder::new("foo", "bar")
+            .path("/")
+            .domain("hyper.rs")
+            .max_age(Duration::from_secs(100))
+            .http_only(true)
+            .same_site(crate::SameSite::LAX)
+            .build()
+            .unwrap();
+
+        assert_eq!(super::serialized_len(&c), c.to_string().len());
+    }
 }
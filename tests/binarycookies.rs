@@ -0,0 +1,70 @@
+#![cfg(feature = "jar")]
+
+use std::time::{Duration, UNIX_EPOCH};
+
+use cookies::{binarycookies, Cookie, CookieJar};
+
+const FIXTURE: &[u8] = include_bytes!("fixtures/Cookies.binarycookies");
+
+#[test]
+fn parse_fixture() {
+    let cookies = binarycookies::parse(FIXTURE).expect("parse fixture");
+    assert_eq!(cookies.len(), 3);
+
+    let mut jar = CookieJar::new();
+    for cookie in cookies {
+        jar.insert(cookie).unwrap();
+    }
+
+    let session = jar.get("hyper.rs", "/", "session").unwrap();
+    assert_eq!(session.value(), "abc");
+    assert!(!session.host_only());
+    assert!(session.secure());
+    assert!(session.http_only());
+    assert_eq!(
+        session.expires(),
+        Some(UNIX_EPOCH + Duration::from_secs(4_102_444_800))
+    );
+    assert_eq!(
+        session.creation_time(),
+        UNIX_EPOCH + Duration::from_millis(1_690_000_000_500)
+    );
+
+    let lang = jar.get("www.hyper.rs", "/guides", "lang").unwrap();
+    assert!(lang.host_only());
+    assert!(!lang.secure());
+    assert!(!lang.http_only());
+
+    let a = jar.get("rust-lang.org", "/", "a").unwrap();
+    assert!(a.secure());
+}
+
+#[test]
+fn write_roundtrip() {
+    let cookies = binarycookies::parse(FIXTURE).unwrap();
+
+    let mut out = Vec::new();
+    binarycookies::write(&cookies, &mut out).unwrap();
+
+    let reparsed = binarycookies::parse(&out).unwrap();
+    assert_eq!(reparsed.len(), cookies.len());
+    for (a, b) in cookies.iter().zip(&reparsed) {
+        assert_eq!(a.name(), b.name());
+        assert_eq!(a.value(), b.value());
+        assert_eq!(a.host(), b.host());
+        assert_eq!(a.host_only(), b.host_only());
+        assert_eq!(a.path(), b.path());
+        assert_eq!(a.secure(), b.secure());
+        assert_eq!(a.http_only(), b.http_only());
+        assert_eq!(a.expires(), b.expires());
+        assert_eq!(a.creation_time(), b.creation_time());
+    }
+}
+
+#[test]
+fn truncated_fixture() {
+    // In the header, the page sizes, a page, a record, and the checksum.
+    for len in [0, 3, 10, 20, 120, 295] {
+        binarycookies::parse(&FIXTURE[..len]).expect_err("truncated");
+    }
+}
@@ -0,0 +1,64 @@
+#![cfg(feature = "chromium")]
+
+use std::time::{Duration, UNIX_EPOCH};
+
+use cookies::{chromium, Cookie, CookieJar, Priority, SameSite};
+
+const FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/chromium-cookies.sqlite"
+);
+
+#[test]
+fn read_fixture() {
+    let results = chromium::read(FIXTURE).expect("read fixture");
+    assert_eq!(results.len(), 4);
+
+    let mut jar = CookieJar::new();
+    let mut errors = Vec::new();
+    for result in results {
+        match result {
+            Ok(cookie) => jar.insert(cookie).unwrap(),
+            Err(err) => errors.push(err),
+        }
+    }
+
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].is_unsupported());
+    assert!(errors[0].to_string().contains("\"keyring\""));
+
+    let session = jar.get("hyper.rs", "/", "session").unwrap();
+    assert_eq!(session.value(), "abc", "decrypted v10");
+    assert!(!session.host_only());
+    assert!(session.secure());
+    assert!(session.http_only());
+    assert_eq!(session.same_site(), Some(SameSite::LAX));
+    assert_eq!(session.priority(), Some(Priority::HIGH));
+    assert_eq!(
+        session.expires(),
+        Some(UNIX_EPOCH + Duration::from_secs(4_102_444_800))
+    );
+    assert_eq!(
+        session.creation_time(),
+        UNIX_EPOCH + Duration::from_secs(1_690_000_000)
+    );
+
+    let lang = jar.get("www.hyper.rs", "/guides", "lang").unwrap();
+    assert_eq!(lang.value(), "en", "plaintext");
+    assert!(lang.host_only());
+    assert!(!lang.is_persistent());
+    assert_eq!(lang.same_site(), None);
+    assert_eq!(lang.priority(), None, "default Medium");
+
+    let widget = jar
+        .get_partitioned("https://hyper.rs", "widget.example", "/", "widget")
+        .unwrap();
+    assert_eq!(widget.value(), "1");
+    assert_eq!(widget.same_site(), Some(SameSite::NONE));
+    assert_eq!(widget.priority(), Some(Priority::LOW));
+}
+
+#[test]
+fn missing_file() {
+    chromium::read("/does/not/exist/Cookies").expect_err("missing file");
+}
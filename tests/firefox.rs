@@ -0,0 +1,65 @@
+#![cfg(feature = "firefox")]
+
+use std::time::{Duration, UNIX_EPOCH};
+
+use cookies::{firefox, Cookie, CookieJar, SameSite};
+
+const FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/firefox-cookies.sqlite"
+);
+
+#[test]
+fn read_fixture() {
+    let results = firefox::read(FIXTURE).expect("read fixture");
+    assert_eq!(results.len(), 5);
+
+    let mut jar = CookieJar::new();
+    let mut errors = Vec::new();
+    for result in results {
+        match result {
+            Ok(cookie) => jar.insert(cookie).unwrap(),
+            Err(err) => errors.push(err),
+        }
+    }
+
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].to_string().contains("\"bad name\""));
+
+    let session = jar.get("hyper.rs", "/", "session").unwrap();
+    assert!(!session.host_only());
+    assert!(session.secure());
+    assert!(session.http_only());
+    assert_eq!(session.same_site(), Some(SameSite::LAX));
+    assert_eq!(
+        session.expires(),
+        Some(UNIX_EPOCH + Duration::from_secs(4_102_444_800))
+    );
+    assert_eq!(
+        session.creation_time(),
+        UNIX_EPOCH + Duration::from_secs(1_690_000_000)
+    );
+
+    let lang = jar.get("www.hyper.rs", "/guides", "lang").unwrap();
+    assert!(lang.host_only());
+    assert_eq!(lang.same_site(), Some(SameSite::STRICT));
+
+    let millis = jar.get("hyper.rs", "/", "ms").unwrap();
+    assert_eq!(
+        millis.expires(),
+        Some(UNIX_EPOCH + Duration::from_secs(4_102_444_800)),
+        "expiry in milliseconds"
+    );
+    assert_eq!(millis.same_site(), None, "unspecified, Lax by default");
+
+    assert!(jar.get("widget.example", "/", "widget").is_none());
+    let widget = jar
+        .get_partitioned("https://hyper.rs", "widget.example", "/", "widget")
+        .unwrap();
+    assert!(widget.partitioned());
+}
+
+#[test]
+fn missing_file() {
+    firefox::read("/does/not/exist/cookies.sqlite").expect_err("missing file");
+}
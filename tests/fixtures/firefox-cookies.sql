@@ -0,0 +1,37 @@
+-- Generates firefox-cookies.sqlite, with the `moz_cookies` schema of
+-- Firefox 132. Regenerate it with:
+--
+--     rm -f tests/fixtures/firefox-cookies.sqlite
+--     sqlite3 tests/fixtures/firefox-cookies.sqlite < tests/fixtures/firefox-cookies.sql
+--
+-- `sameSite` is what Firefox enforces, `rawSameSite` the attribute as it was
+-- set, with 0 when it was unspecified. The `ms` cookie has no attribute, and
+-- is Lax by default.
+
+CREATE TABLE moz_cookies (
+    id INTEGER PRIMARY KEY,
+    originAttributes TEXT NOT NULL DEFAULT '',
+    name TEXT,
+    value TEXT,
+    host TEXT,
+    path TEXT,
+    expiry INTEGER,
+    lastAccessed INTEGER,
+    creationTime INTEGER,
+    isSecure INTEGER,
+    isHttpOnly INTEGER,
+    inBrowserElement INTEGER DEFAULT 0,
+    sameSite INTEGER DEFAULT 0,
+    rawSameSite INTEGER DEFAULT 0,
+    schemeMap INTEGER DEFAULT 0,
+    isPartitionedAttributeSet INTEGER DEFAULT 0,
+    CONSTRAINT moz_uniqueid UNIQUE (name, host, path, originAttributes)
+);
+
+INSERT INTO moz_cookies VALUES (1, '', 'session', 'abc', '.hyper.rs', '/', 4102444800, 1700000000000000, 1690000000000000, 1, 1, 0, 1, 1, 2, 0);
+INSERT INTO moz_cookies VALUES (2, '', 'lang', 'en', 'www.hyper.rs', '/guides', 4102444800, 1700000000000000, 1690000000000000, 0, 0, 0, 2, 2, 1, 0);
+-- Expiry in milliseconds.
+INSERT INTO moz_cookies VALUES (3, '', 'ms', '1', 'hyper.rs', '/', 4102444800000, 1700000000000000, 1690000000000000, 0, 0, 0, 1, 0, 1, 0);
+INSERT INTO moz_cookies VALUES (4, '^partitionKey=%28https%2Chyper.rs%29', 'widget', '1', 'widget.example', '/', 4102444800, 1700000000000000, 1690000000000000, 1, 0, 0, 0, 0, 2, 1);
+-- Not a valid cookie name.
+INSERT INTO moz_cookies VALUES (5, '', 'bad name', '1', 'hyper.rs', '/', 4102444800, 1700000000000000, 1690000000000000, 0, 0, 0, 0, 0, 1, 0);
@@ -0,0 +1,64 @@
+#![cfg(feature = "reqwest")]
+
+use std::sync::Arc;
+
+use cookies::{CookieJar, SharedCookieJar};
+
+mod support;
+
+use support::{Request, Response};
+
+fn app(req: &Request) -> Response {
+    let cookie = req.cookie.clone().unwrap_or_default();
+    match &req.path[..] {
+        "/login" => Response {
+            status: 302,
+            headers: vec![
+                ("Set-Cookie", "session=abc; Path=/; HttpOnly".into()),
+                ("Set-Cookie", "theme=dark; Path=/app".into()),
+                ("Set-Cookie", "tracker=1; Domain=com".into()),
+                ("Set-Cookie", "token=x; Secure".into()),
+                ("Location", "/app/echo".into()),
+            ],
+            body: String::new(),
+        },
+        "/logout" => Response {
+            status: 200,
+            headers: vec![("Set-Cookie", "session=; Max-Age=0".into())],
+            body: String::new(),
+        },
+        _ => Response {
+            status: 200,
+            headers: Vec::new(),
+            body: cookie,
+        },
+    }
+}
+
+#[tokio::test]
+async fn client_session() {
+    let addr = support::serve(app);
+    let url = |path: &str| format!("http://{}{}", addr, path);
+
+    let jar = SharedCookieJar::from(CookieJar::new());
+    let client = reqwest::Client::builder()
+        .cookie_provider(Arc::new(jar.clone()))
+        .build()
+        .unwrap();
+
+    // The redirect is followed with the cookies it set.
+    let res = client.get(url("/login")).send().await.unwrap();
+    assert_eq!(res.url().path(), "/app/echo");
+    assert_eq!(res.text().await.unwrap(), "theme=dark; session=abc");
+
+    let res = client.get(url("/")).send().await.unwrap();
+    assert_eq!(res.text().await.unwrap(), "session=abc");
+
+    // The cookie for a public suffix was rejected, and the `Secure` one
+    // isn't sent over http.
+    assert_eq!(jar.read().len(), 3);
+
+    client.get(url("/logout")).send().await.unwrap();
+    let res = client.get(url("/app/echo")).send().await.unwrap();
+    assert_eq!(res.text().await.unwrap(), "theme=dark");
+}
@@ -0,0 +1,54 @@
+#![cfg(feature = "jar")]
+
+use std::thread;
+
+use cookies::{RequestContext, SharedCookieJar};
+use url::Url;
+
+const THREADS: usize = 8;
+const ROUNDS: usize = 500;
+
+#[test]
+fn concurrent_stores_and_queries() {
+    let jar = SharedCookieJar::new();
+    let shared = Url::parse("https://hyper.rs/").unwrap();
+
+    let handles = (0..THREADS)
+        .map(|t| {
+            let jar = jar.clone();
+            let shared = shared.clone();
+            thread::spawn(move || {
+                let own = Url::parse(&format!("https://t{}.hyper.rs/", t)).unwrap();
+                let ctx = RequestContext::new();
+                for i in 0..ROUNDS {
+                    // Both cookies of a response are stored at once, so no
+                    // query sees one updated without the other.
+                    let a = format!("a={}-{}; Domain=hyper.rs", t, i);
+                    let b = format!("b={}-{}; Domain=hyper.rs", t, i);
+                    jar.store_set_cookies(&shared, [a, b]);
+                    jar.store_set_cookie(&own, &format!("n{}={}", i % 10, i))
+                        .unwrap();
+
+                    let header = jar.cookie_header(&shared, &ctx).unwrap();
+                    let pairs = cookies::parse_pairs(&header).collect::<Vec<_>>();
+                    assert_eq!(pairs.len(), 2, "{}", header);
+                    assert_eq!(pairs[0].1, pairs[1].1, "{}", header);
+
+                    let header = jar.cookie_header(&own, &ctx).unwrap();
+                    let own_count = cookies::parse_pairs(&header).count();
+                    assert_eq!(own_count, 2 + (i + 1).min(10), "{}", header);
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let jar = jar.read();
+    assert_eq!(jar.len(), 2 + THREADS * 10);
+    let a = jar.get("hyper.rs", "/", "a").unwrap();
+    let b = jar.get("hyper.rs", "/", "b").unwrap();
+    assert_eq!(cookies::Cookie::value(a), cookies::Cookie::value(b));
+}
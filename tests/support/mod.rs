@@ -0,0 +1,70 @@
+//! A minimal HTTP/1.1 server on the loopback interface, to test the client
+//! integrations without any outside network.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::thread;
+
+pub struct Request {
+    pub path: String,
+    pub cookie: Option<String>,
+}
+
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: String,
+}
+
+/// Serve each connection with `handler` on a background thread, and get
+/// the address to connect to.
+pub fn serve(handler: fn(&Request) -> Response) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(&stream);
+
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let path = line.split(' ').nth(1).unwrap_or("/").to_owned();
+            let mut cookie = None;
+            let mut content_length = 0;
+            loop {
+                line.clear();
+                reader.read_line(&mut line).unwrap();
+                let header = line.trim_end();
+                if header.is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.eq_ignore_ascii_case("cookie") {
+                        cookie = Some(value.trim().to_owned());
+                    } else if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+
+            // The body is read, but not passed to the handler.
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            let res = handler(&Request { path, cookie });
+            let mut head = format!("HTTP/1.1 {} X\r\n", res.status);
+            for (name, value) in &res.headers {
+                head.push_str(&format!("{}: {}\r\n", name, value));
+            }
+            head.push_str(&format!(
+                "Content-Length: {}\r\nConnection: close\r\n\r\n",
+                res.body.len()
+            ));
+            stream.write_all(head.as_bytes()).unwrap();
+            stream.write_all(res.body.as_bytes()).unwrap();
+        }
+    });
+
+    addr
+}
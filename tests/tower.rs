@@ -0,0 +1,73 @@
+#![cfg(feature = "tower")]
+
+use std::future::poll_fn;
+
+use cookies::tower::{CookieLayer, CookieService};
+use cookies::SharedCookieJar;
+use http_body_util::{BodyExt, Empty};
+use hyper::body::Bytes;
+use hyper::header::LOCATION;
+use hyper::Request;
+use hyper_util::client::legacy::{connect::HttpConnector, Client};
+use hyper_util::rt::TokioExecutor;
+use tower_layer::Layer;
+use tower_service::Service;
+
+mod support;
+
+use support::Response;
+
+type Svc = CookieService<Client<HttpConnector, Empty<Bytes>>>;
+
+fn app(req: &support::Request) -> Response {
+    let redirect = |location: &str, cookie: &str| Response {
+        status: 302,
+        headers: vec![("Set-Cookie", cookie.into()), ("Location", location.into())],
+        body: String::new(),
+    };
+    match &req.path[..] {
+        "/login" => redirect("/step", "session=abc; Path=/"),
+        "/step" => redirect("/app/echo", "step=1; Path=/app"),
+        _ => Response {
+            status: 200,
+            headers: Vec::new(),
+            body: req.cookie.clone().unwrap_or_default(),
+        },
+    }
+}
+
+/// Get a URL, following redirects, and get the body.
+async fn get(svc: &mut Svc, url: String) -> String {
+    let mut uri = url.parse::<hyper::Uri>().unwrap();
+    loop {
+        poll_fn(|cx| svc.poll_ready(cx)).await.unwrap();
+        let req = Request::get(uri.clone()).body(Empty::new()).unwrap();
+        let res = svc.call(req).await.unwrap();
+
+        if let Some(location) = res.headers().get(LOCATION) {
+            let path = location.to_str().unwrap();
+            uri = format!("http://{}{}", uri.authority().unwrap(), path)
+                .parse()
+                .unwrap();
+            continue;
+        }
+        let body = res.into_body().collect().await.unwrap().to_bytes();
+        return String::from_utf8(body.to_vec()).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn hyper_client_redirects() {
+    let addr = support::serve(app);
+
+    let jar = SharedCookieJar::new();
+    let client = Client::builder(TokioExecutor::new()).build_http();
+    let mut svc = CookieLayer::new(jar.clone()).layer(client);
+
+    let body = get(&mut svc, format!("http://{}/login", addr)).await;
+    assert_eq!(body, "step=1; session=abc");
+    assert_eq!(jar.read().len(), 2);
+
+    let body = get(&mut svc, format!("http://{}/", addr)).await;
+    assert_eq!(body, "session=abc");
+}
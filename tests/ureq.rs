@@ -0,0 +1,146 @@
+#![cfg(feature = "ureq")]
+
+use cookies::ureq::{CookieAgent, CookieMiddleware};
+use cookies::SharedCookieJar;
+
+mod support;
+
+use support::{Request, Response};
+
+fn app(req: &Request) -> Response {
+    let redirect = |location: &str, cookie: &str| Response {
+        status: 302,
+        headers: vec![("Set-Cookie", cookie.into()), ("Location", location.into())],
+        body: String::new(),
+    };
+    if let Some(port) = req.path.strip_prefix("/cross/") {
+        // The same server, as another host.
+        let location = format!("http://localhost:{}/landed", port);
+        return redirect(&location, "origin=1; Path=/");
+    }
+    match &req.path[..] {
+        "/login" => redirect("/step", "session=abc; Path=/"),
+        "/landed" => Response {
+            status: 200,
+            headers: vec![("Set-Cookie", "landed=1; Path=/".into())],
+            body: String::new(),
+        },
+        "/step" => redirect("/app/echo", "step=1; Path=/app"),
+        "/loop" => redirect("/loop", "loop=1"),
+        "/denied" => Response {
+            status: 403,
+            headers: vec![("Set-Cookie", "denied=1".into())],
+            body: String::new(),
+        },
+        _ => Response {
+            status: 200,
+            headers: vec![("Set-Cookie", "seen=1; Path=/app".into())],
+            body: req.cookie.clone().unwrap_or_default(),
+        },
+    }
+}
+
+#[test]
+fn agent_follows_redirects() {
+    let addr = support::serve(app);
+    let url = |path: &str| format!("http://{}{}", addr, path);
+
+    let jar = SharedCookieJar::new();
+    let agent = CookieAgent::new(jar.clone());
+
+    let mut res = agent.get(&url("/login")).unwrap();
+    assert_eq!(
+        res.body_mut().read_to_string().unwrap(),
+        "step=1; session=abc"
+    );
+
+    let mut res = agent.get(&url("/app/echo")).unwrap();
+    assert_eq!(
+        res.body_mut().read_to_string().unwrap(),
+        "step=1; seen=1; session=abc"
+    );
+    assert_eq!(jar.read().len(), 3);
+
+    let err = agent.get(&url("/loop")).unwrap_err();
+    assert!(matches!(err, ureq::Error::TooManyRedirects), "{}", err);
+
+    let err = agent.get(&url("/denied")).unwrap_err();
+    assert!(matches!(err, ureq::Error::StatusCode(403)), "{}", err);
+    assert_eq!(jar.read().len(), 5, "error responses store cookies");
+
+    let config = ureq::Agent::config_builder().max_redirects(0).build();
+    let agent = CookieAgent::with_agent(config.new_agent(), SharedCookieJar::new());
+    let res = agent.get(&url("/login")).unwrap();
+    assert_eq!(res.status(), 302);
+    assert_eq!(agent.jar().read().len(), 1);
+}
+
+#[test]
+fn agent_returns_redirect_of_body() {
+    let addr = support::serve(app);
+    let url = |path: &str| format!("http://{}{}", addr, path);
+
+    let agent = CookieAgent::new(SharedCookieJar::new());
+    for method in ["PUT", "PATCH"] {
+        let request = ureq::http::Request::builder()
+            .method(method)
+            .uri(url("/login"))
+            .body("data")
+            .unwrap();
+        let res = agent.run(request).unwrap();
+        assert_eq!(
+            res.status(),
+            302,
+            "{} isn't resent without its body",
+            method
+        );
+    }
+    assert_eq!(agent.jar().read().len(), 1);
+
+    let request = ureq::http::Request::post(url("/login"))
+        .body("data")
+        .unwrap();
+    let res = agent.run(request).unwrap();
+    assert_eq!(res.status(), 200, "POST is redirected as GET");
+}
+
+#[test]
+fn middleware() {
+    let addr = support::serve(app);
+    let url = |path: &str| format!("http://{}{}", addr, path);
+
+    let jar = SharedCookieJar::new();
+    let agent = ureq::Agent::config_builder()
+        .middleware(CookieMiddleware::new(jar.clone()))
+        .build()
+        .new_agent();
+
+    agent.get(&url("/app/echo")).call().unwrap();
+    let mut res = agent
+        .get(&url("/app/echo"))
+        .header("Cookie", "manual=1")
+        .call()
+        .unwrap();
+    assert_eq!(res.body_mut().read_to_string().unwrap(), "manual=1; seen=1");
+}
+
+#[test]
+fn middleware_stores_for_final_url() {
+    let addr = support::serve(app);
+
+    let jar = SharedCookieJar::new();
+    let agent = ureq::Agent::config_builder()
+        .middleware(CookieMiddleware::new(jar.clone()))
+        .build()
+        .new_agent();
+
+    let start = format!("http://127.0.0.1:{}/cross/{}", addr.port(), addr.port());
+    agent.get(&start).call().unwrap();
+
+    let jar = jar.read();
+    assert!(jar.get("localhost", "/", "landed").is_some());
+    assert!(
+        jar.get("127.0.0.1", "/", "landed").is_none(),
+        "not stored for the first host"
+    );
+}